@@ -1,7 +1,8 @@
-use std::{collections::HashMap, env};
-use crate::print_utils;
+use std::{collections::HashMap, env, str::FromStr};
+use rustic_notes::{Result, RusticError};
 
-type CliCommandAction = fn(HashMap<String, Vec<String>>);
+type CliCommandAction = fn(ParsedArgs) -> Result<()>;
+type CliOptionValidator = fn(&str) -> Result<()>;
 
 #[derive(Default)]
 pub struct CliCommandBuilder {
@@ -9,9 +10,10 @@ pub struct CliCommandBuilder {
     aliases: Vec<String>,
     description: Option<String>,
     version: Option<String>,
-    arguments: Vec<String>,
+    arguments: Vec<CliCommandArgument>,
     subcommands: Vec<CliCommand>,
     options: Vec<CliCommandOption>,
+    global_options: Vec<CliCommandOption>,
     action: Option<CliCommandAction>,
 }
 
@@ -36,8 +38,9 @@ impl CliCommandBuilder {
         self
     }
 
-    pub fn add_argument(&mut self, argument: &str) -> &mut Self {
-        self.arguments.push(argument.to_string());
+    // arguments are filled in the order they are added, a variadic one has to be the last
+    pub fn add_argument(&mut self, argument: &CliCommandArgument) -> &mut Self {
+        self.arguments.push(argument.clone());
         self
     }
 
@@ -51,13 +54,24 @@ impl CliCommandBuilder {
         self
     }
 
+    pub fn add_options(&mut self, options: &[CliCommandOption]) -> &mut Self {
+        self.options.extend(options.iter().cloned());
+        self
+    }
+
+    // global options are accepted by this command and every one of its subcommands
+    pub fn add_global_option(&mut self, option: &CliCommandOption) -> &mut Self {
+        self.global_options.push(option.clone());
+        self
+    }
+
     pub fn set_action(&mut self, action: CliCommandAction) -> &mut Self {
         self.action = Some(action.to_owned());
         self
     }
 
     pub fn build(&self) -> CliCommand {
-        CliCommand {
+        let mut command = CliCommand {
             name: self.name.clone(),
             aliases: self.aliases.clone(),
             description: self.description.clone(),
@@ -66,7 +80,9 @@ impl CliCommandBuilder {
             subcommands: self.subcommands.clone(),
             options: self.options.clone(),
             action: self.action,
-        }
+        };
+        command.inherit_options(&self.global_options);
+        command
     }
 }
 
@@ -77,45 +93,52 @@ pub struct CliCommand {
     pub aliases: Vec<String>,
     pub description: Option<String>,
     pub version: Option<String>,
-    pub arguments: Vec<String>,
+    pub arguments: Vec<CliCommandArgument>,
     pub subcommands: Vec<CliCommand>,
     pub options: Vec<CliCommandOption>,
     pub action: Option<CliCommandAction>,
 }
 
 impl CliCommand {
-    pub fn run(&self, args: env::Args) {
+    pub fn run(&self, args: env::Args) -> Result<()> {
         let env_args: Vec<String> = args.skip(1).collect();
-        let command = select_command(env_args.clone(), self);
-        if command.is_none() {
-            return;
-        }
-        let command = command.unwrap();
+        let (command, command_indices) = select_command(env_args.clone(), self)?;
 
-        if search_for_help_flag(env_args.clone()) {
+        if search_for_flag(&env_args, command, &["--help", "-h"]) {
             self.get_version();
             command.get_help();
-            return;
+            return Ok(());
         }
 
-        if search_for_version_flag(env_args.clone()) {
+        if search_for_flag(&env_args, command, &["--version", "-V"]) {
             self.get_version();
-            return;
+            return Ok(());
         }
 
         // remove arguments that choose a subcommand
-        let command_index = env_args.iter().position(|arg| *arg == command.name).unwrap_or(0);
         let env_args: Vec<String> = env_args.into_iter().enumerate()
-            .filter(|(index, arg)| arg.starts_with("-") || *index > command_index)
+            .filter(|(index, _)| !command_indices.contains(index))
             .map(|(_, arg)| arg)
             .collect();
 
-        let arguments = collect_arguments(env_args, command);
+        let arguments = collect_arguments(env_args, command)?;
 
-        if command.action.is_some() {
-            (command.action.unwrap())(get_arguments_map(arguments));
+        if let Some(action) = command.action {
+            action(ParsedArgs::new(arguments))
         } else {
             command.get_help();
+            Ok(())
+        }
+    }
+
+    fn inherit_options(&mut self, options: &[CliCommandOption]) {
+        for option in options {
+            if !self.options.iter().any(|o| o.name == option.name) {
+                self.options.push(option.clone());
+            }
+        }
+        for subcommand in self.subcommands.iter_mut() {
+            subcommand.inherit_options(options);
         }
     }
 
@@ -126,6 +149,7 @@ impl CliCommand {
         self.print_help_usage(padding_width);
         self.print_help_example(padding_width);
         self.print_help_subcommands(padding_width);
+        self.print_help_arguments(padding_width);
         self.print_help_options(padding_width);
     }
 
@@ -140,7 +164,8 @@ impl CliCommand {
     fn print_help_usage(&self, padding_width: usize) {
         println!();
         println!("USAGE");
-        println!("{padding}$ {0}{1}{2}", self.name, if self.subcommands.is_empty() { "" } else if self.action.is_none() { " [COMMAND]" } else { " COMMAND" }, if self.options.is_empty() { "" } else { " [OPTIONS]" }, padding = " ".repeat(padding_width));
+        let arguments: String = self.arguments.iter().map(|argument| format!(" {}", argument.usage())).collect();
+        println!("{padding}$ {0}{1}{arguments}{2}", self.name, if self.subcommands.is_empty() { "" } else if self.action.is_none() { " [COMMAND]" } else { " COMMAND" }, if self.options.is_empty() { "" } else { " [OPTIONS]" }, padding = " ".repeat(padding_width));
     }
 
     fn print_help_example(&self, padding_width: usize) {
@@ -182,6 +207,26 @@ impl CliCommand {
         println!("{padding}Use \"{} COMMAND --help\" for more information about a command.", self.name, padding = " ".repeat(padding_width));
     }
 
+    fn print_help_arguments(&self, padding_width: usize) {
+        if self.arguments.is_empty() {
+            return;
+        }
+
+        println!();
+        println!("ARGUMENTS");
+
+        let longest_usage_len = self.arguments
+            .iter()
+            .map(|argument| argument.usage().len())
+            .max()
+            .unwrap_or(0);
+
+        let padding = " ".repeat(padding_width);
+        for argument in &self.arguments {
+            println!("{padding}{:<longest_usage_len$} - {}", argument.usage(), argument.description.as_deref().unwrap_or(""));
+        }
+    }
+
     fn print_help_options(&self, padding_width: usize) {
         if self.options.is_empty() {
             return;
@@ -190,17 +235,17 @@ impl CliCommand {
         println!();
         println!("OPTIONS");
 
-        let display_items: Vec<(String, &str)> = self.options
+        let display_items: Vec<(String, String)> = self.options
             .iter()
             .map(|option| {
-                let short_name = option.short_name.as_ref().map_or(String::new(), |s| format!("-{s}, "));
-                let name = if option.is_flag {
-                    format!("{short_name}--{}, ", option.name)
-                } else {
-                    format!("{short_name}--{} <value>, ", option.name)
-                };
-                let description = option.description.as_deref().unwrap_or("");
-                (name, description)
+                let mut description = option.description.clone().unwrap_or_default();
+                if option.is_required {
+                    description.push_str(" (required)");
+                }
+                if let Some(default_value) = &option.default_value {
+                    description.push_str(&format!(" (default: {default_value})"));
+                }
+                (format!("{}, ", option.usage()), description)
             })
             .collect();
 
@@ -221,101 +266,374 @@ impl CliCommand {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[derive(Clone)]
 pub struct CliCommandOption {
     pub name: String,
     pub short_name: Option<String>,
     pub is_flag: bool,
     pub description: Option<String>,
+    pub is_required: bool,
+    // used when the option is not given at all
+    pub default_value: Option<String>,
+    pub value_type: ArgumentType,
+    // runs after the value type is checked, for what a type cannot express like dates
+    pub validator: Option<CliOptionValidator>,
 }
 
-fn select_command(env_args: Vec<String>, command: &CliCommand) -> Option<&CliCommand> {
-    if env_args.is_empty() {
-        return Some(command);
+impl CliCommandOption {
+    // `--format <dot|json>`, `-t, --tag <value>` or `--force`
+    fn usage(&self) -> String {
+        let short_name = self.short_name.as_ref().map_or(String::new(), |s| format!("-{s}, "));
+        match &self.value_type {
+            _ if self.is_flag => format!("{short_name}--{}", self.name),
+            ArgumentType::Choice(choices) => format!("{short_name}--{} <{}>", self.name, choices.join("|")),
+            _ => format!("{short_name}--{} <value>", self.name),
+        }
     }
 
-    let mut cmd = command;
+    fn validate(&self, value: &str) -> Result<()> {
+        self.value_type.validate(&self.name, value)?;
+        match self.validator {
+            Some(validator) => validator(value),
+            None => Ok(()),
+        }
+    }
+}
 
-    for arg in env_args.clone() {
-        if !arg.starts_with("-") {
-            if let Some(subcommand) = search_command(&arg, cmd) {
-                cmd = subcommand;
-            } else if cmd.arguments.is_empty() {
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Command '{}' not found.", arg).as_str()));
-                println!("Please refer to --help for '{}' command.", cmd.name);
-                return None
-            }
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct CliCommandArgument {
+    pub name: String,
+    pub description: Option<String>,
+    pub value_type: ArgumentType,
+    pub is_required: bool,
+    // takes every remaining positional value, e.g. all the words of `new buy milk`
+    pub is_variadic: bool,
+}
+
+impl CliCommandArgument {
+    // `<id>` for required arguments, `[rev]` for optional ones and `...` after variadic ones
+    fn usage(&self) -> String {
+        let usage = if self.is_required { format!("<{}>", self.name) } else { format!("[{}]", self.name) };
+        if self.is_variadic { format!("{usage}...") } else { usage }
+    }
+}
+
+#[derive(Debug, Default)]
+#[derive(Clone, PartialEq)]
+pub enum ArgumentType {
+    #[default]
+    Text,
+    // an unsigned number like a note id or revision
+    Number,
+    Path,
+    Choice(Vec<String>),
+    // words of the search query language, where `-word` or `-tag:work` negates a term instead of being an option
+    Query,
+}
+
+impl ArgumentType {
+    fn validate(&self, name: &str, value: &str) -> Result<()> {
+        match self {
+            ArgumentType::Text | ArgumentType::Query => Ok(()),
+            ArgumentType::Number => value.parse::<u32>()
+                .map(|_| ())
+                .map_err(|_| RusticError::Usage(format!("Invalid {name}: {value}, expected a number."))),
+            ArgumentType::Path if value.is_empty() => Err(RusticError::Usage(format!("Invalid {name}: the path is empty."))),
+            ArgumentType::Path => Ok(()),
+            ArgumentType::Choice(choices) if choices.iter().any(|choice| choice == value) => Ok(()),
+            ArgumentType::Choice(choices) => Err(RusticError::Usage(format!("Invalid {name}: {value}, expected one of {}.", choices.join(", ")))),
+        }
+    }
+}
+
+/// Values a command was run with, by the name of their option or argument.
+///
+/// The values are checked against their definitions before the action runs, so parsing them to the
+/// type they were declared with does not fail.
+#[derive(Debug, Default)]
+pub struct ParsedArgs {
+    values: HashMap<String, Vec<String>>,
+}
+
+impl ParsedArgs {
+    fn new(arguments: Vec<(String, Option<String>)>) -> Self {
+        let mut values: HashMap<String, Vec<String>> = HashMap::new();
+        for (name, value) in arguments {
+            values.entry(name).or_default().push(value.unwrap_or_default());
+        }
+        ParsedArgs { values }
+    }
+
+    // the last value given, a repeated option overrides the earlier ones
+    pub fn get_one<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        self.values.get(name)
+            .and_then(|values| values.last())
+            .map(|value| parse_value(name, value))
+            .transpose()
+    }
+
+    pub fn get_required<T: FromStr>(&self, name: &str) -> Result<T> {
+        self.get_one(name)?.ok_or_else(|| RusticError::Usage(format!("Missing {}.", name.replace('_', " "))))
+    }
+
+    // every value of a repeated option or variadic argument, in the order given
+    pub fn get_many<T: FromStr>(&self, name: &str) -> Result<Vec<T>> {
+        self.values.get(name).map_or(Ok(vec![]), |values| values.iter().map(|value| parse_value(name, value)).collect())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T> {
+    value.parse().map_err(|_| RusticError::Usage(format!("Invalid {name}: {value}")))
+}
+
+// returns the selected command together with the indices of the arguments naming it
+fn select_command(env_args: Vec<String>, command: &CliCommand) -> Result<(&CliCommand, Vec<usize>)> {
+    let mut cmd = command;
+    let mut command_indices = vec![];
+    let mut expects_option_value = false;
+
+    for (index, arg) in env_args.into_iter().enumerate() {
+        if arg == "--" {
+            // everything after it is positional
+            break;
+        } else if expects_option_value {
+            // value of the previous option, not a command
+            expects_option_value = false;
+        } else if arg.starts_with("-") && arg != "-" {
+            // options of subcommands further down are not known yet, those are checked once the command is selected
+            expects_option_value = takes_next_value(&arg, cmd);
+        } else if let Some(subcommand) = search_command(&arg, cmd) {
+            cmd = subcommand;
+            command_indices.push(index);
+        } else if cmd.arguments.is_empty() {
+            return Err(RusticError::Usage(format!("Command '{}' not found. Please refer to --help for '{}' command.", arg, cmd.name)));
         }
     }
 
     // If no subcommand matches, return the root command
-    Some(cmd)
+    Ok((cmd, command_indices))
 }
 
-fn collect_arguments(env_args: Vec<String>, command: &CliCommand) -> Vec<(String, Option<String>)> {
+fn collect_arguments(env_args: Vec<String>, command: &CliCommand) -> Result<Vec<(String, Option<String>)>> {
     let mut args: Vec<(String, Option<String>)> = vec![];
-    let mut previous_argument_definition: Option<&CliCommandOption> = None;
-    let mut positional_index = 0;
-
-    for arg in env_args.clone() {
-        if arg.starts_with("--") {
-            let arg_key = arg.trim_start_matches("--").to_string();
-            let option_definition = search_command_options(arg_key.as_str(), command);
-            if option_definition.is_some() {
-                args.push((option_definition.unwrap().name.clone(), if option_definition.unwrap().is_flag { Some(String::from("true")) } else { None }));
-                previous_argument_definition = option_definition;
-            }
-        } else if arg.starts_with("-") {
-            let arg_key = arg.trim_start_matches("-").to_string();
-            let option_definition = search_command_options(arg_key.as_str(), command);
-            if option_definition.is_some() {
-                args.push((option_definition.unwrap().name.clone(), if option_definition.unwrap().is_flag { Some(String::from("true")) } else { None }));
-                previous_argument_definition = option_definition;
-            }
+    let mut positional_values: Vec<String> = vec![];
+    let mut option_expecting_value: Option<&CliCommandOption> = None;
+    let mut options_ended = false;
+
+    for arg in env_args {
+        if let Some(option) = option_expecting_value.take() {
+            args.push((option.name.clone(), Some(arg)));
+        } else if options_ended || arg == "-" || !arg.starts_with("-") || is_negated_query_word(&arg, command) {
+            positional_values.push(arg);
+        } else if arg == "--" {
+            options_ended = true;
         } else {
-            if previous_argument_definition.is_none() {
-                let argument_definition = command.arguments.get(positional_index);
-                if let Some(argument_definition) = argument_definition {
-                    args.push((argument_definition.clone(), Some(arg.clone())));
-                    positional_index += 1;
+            for (option, value) in parse_option(&arg, command)? {
+                match value {
+                    Some(value) => args.push((option.name.clone(), Some(value))),
+                    None if option.is_flag => args.push((option.name.clone(), Some(String::from("true")))),
+                    None => option_expecting_value = Some(option),
                 }
             }
+        }
+    }
+    if let Some(option) = option_expecting_value {
+        return Err(RusticError::Usage(format!("Option --{} requires a value.", option.name)));
+    }
 
-            if previous_argument_definition.is_some() && !previous_argument_definition.unwrap().is_flag {
-                args.last_mut().unwrap().1 = Some(arg.clone());
-                previous_argument_definition = None;
+    for option in &command.options {
+        let values: Vec<&String> = args.iter().filter(|(name, _)| *name == option.name).filter_map(|(_, value)| value.as_ref()).collect();
+        if values.is_empty() {
+            if option.is_required {
+                return Err(RusticError::Usage(format!("Missing option --{} for '{}'.", option.name, command.name)));
             }
-        };
+            if let Some(default_value) = &option.default_value {
+                args.push((option.name.clone(), Some(default_value.clone())));
+            }
+        } else if !option.is_flag {
+            values.into_iter().try_for_each(|value| option.validate(value))?;
+        }
     }
 
-    args
+    args.extend(assign_positional_values(positional_values, command)?);
+    Ok(args)
+}
+
+// `-oat` or `-tag:archived` in an unquoted query negate a term, only an exact short option like `-t`
+// is read as an option for commands taking a query
+fn is_negated_query_word(arg: &str, command: &CliCommand) -> bool {
+    let has_query_argument = command.arguments.iter().any(|argument| argument.value_type == ArgumentType::Query);
+    let Some(word) = arg.strip_prefix('-').filter(|word| !word.is_empty() && !word.starts_with('-')) else {
+        return false;
+    };
+    let is_short_option = command.options.iter().any(|option| option.short_name.as_deref() == Some(word));
+    has_query_argument && !is_short_option
+}
+
+// whether the argument is an option whose value is the next argument
+fn takes_next_value(arg: &str, command: &CliCommand) -> bool {
+    !is_negated_query_word(arg, command) && parse_option(arg, command).ok()
+        .and_then(|options| options.last().map(|(option, value)| !option.is_flag && value.is_none()))
+        .unwrap_or(false)
 }
 
-fn get_arguments_map(arguments: Vec<(String, Option<String>)>) -> HashMap<String, Vec<String>> {
-    let mut args_hashmap: HashMap<String, Vec<String>> = HashMap::new();
-    for arg in arguments {
-        args_hashmap.entry(arg.0).or_default().push(arg.1.unwrap_or_default());
+// `--name`, `--name=value`, `-n`, `-nvalue` or several short flags like `-it`, options without a value
+// take the next argument as theirs
+fn parse_option<'a>(arg: &str, command: &'a CliCommand) -> Result<Vec<(&'a CliCommandOption, Option<String>)>> {
+    let unknown_option = |name: &str| RusticError::Usage(format!("Unknown option '{name}' for '{}'. Please refer to --help for '{}' command.", command.name, command.name));
+
+    if let Some(long_option) = arg.strip_prefix("--") {
+        let (name, value) = match long_option.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (long_option, None),
+        };
+        let option = command.options.iter().find(|option| option.name == name).ok_or_else(|| unknown_option(arg))?;
+        if option.is_flag && value.is_some() {
+            return Err(RusticError::Usage(format!("Option --{name} does not take a value.")));
+        }
+        return Ok(vec![(option, value)]);
+    }
+
+    let short_options = arg.trim_start_matches('-');
+    let mut options = vec![];
+    for (index, c) in short_options.char_indices() {
+        let name = c.to_string();
+        let option = command.options.iter().find(|option| option.short_name.as_deref() == Some(name.as_str())).ok_or_else(|| unknown_option(&format!("-{c}")))?;
+        if option.is_flag {
+            options.push((option, None));
+            continue;
+        }
+
+        // the rest of the argument is the value, as in `-C3`
+        let value = &short_options[index + c.len_utf8()..];
+        options.push((option, (!value.is_empty()).then(|| value.to_string())));
+        break;
     }
-    args_hashmap
+    Ok(options)
 }
 
-fn search_command<'a>(name: &str, command: &'a CliCommand) -> Option<&'a CliCommand> {
-    if command.name == name {
-        return Some(command);
+// fills the arguments of the command in order and validates their values
+fn assign_positional_values(values: Vec<String>, command: &CliCommand) -> Result<Vec<(String, Option<String>)>> {
+    let mut args: Vec<(String, Option<String>)> = vec![];
+    let mut values = values.into_iter();
+
+    for argument in &command.arguments {
+        let argument_values: Vec<String> = if argument.is_variadic { values.by_ref().collect() } else { values.next().into_iter().collect() };
+        if argument_values.is_empty() && argument.is_required {
+            return Err(RusticError::Usage(format!("Missing argument {} for '{}'.", argument.usage(), command.name)));
+        }
+
+        for value in argument_values {
+            argument.value_type.validate(&argument.name, &value)?;
+            args.push((argument.name.clone(), Some(value)));
+        }
     }
 
-    command.subcommands.iter().find(|&cmd| cmd.name == name || cmd.aliases.contains(&name.to_string()))
+    if let Some(value) = values.next() {
+        return Err(RusticError::Usage(format!("Unexpected argument '{value}' for '{}'.", command.name)));
+    }
+    Ok(args)
 }
 
-fn search_command_options<'a>(name: &str, command: &'a CliCommand) -> Option<&'a CliCommandOption> {
-    command.options.iter().find(|&option| option.name == name || option.short_name.as_deref() == Some(name))
+fn search_command<'a>(name: &str, command: &'a CliCommand) -> Option<&'a CliCommand> {
+    command.subcommands.iter().find(|&cmd| cmd.name == name || cmd.aliases.contains(&name.to_string()))
 }
 
-fn search_for_help_flag(env_args: Vec<String>) -> bool {
-    env_args.iter().any(|arg| arg == "--help" || arg == "-h")
+// only options count, not the words after `--` or the value of another option as in `new -m --help`
+fn search_for_flag(env_args: &[String], command: &CliCommand, names: &[&str]) -> bool {
+    let mut expects_option_value = false;
+    for arg in env_args {
+        if arg == "--" {
+            return false;
+        } else if expects_option_value {
+            expects_option_value = false;
+        } else if names.contains(&arg.as_str()) {
+            return true;
+        } else if arg.starts_with("-") && arg != "-" {
+            expects_option_value = takes_next_value(arg, command);
+        }
+    }
+    false
 }
 
-fn search_for_version_flag(env_args: Vec<String>) -> bool {
-    env_args.iter().any(|arg| arg == "--version" || arg == "-V")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(argument_type: ArgumentType) -> CliCommand {
+        CliCommandBuilder::default()
+            .set_name("test")
+            .add_argument(&CliCommandArgument { name: "words".to_string(), description: None, value_type: argument_type, is_required: false, is_variadic: true })
+            .add_options(&[
+                CliCommandOption { name: "interactive".to_string(), short_name: Some("i".to_string()), is_flag: true, ..Default::default() },
+                CliCommandOption { name: "tag".to_string(), short_name: Some("t".to_string()), ..Default::default() },
+                CliCommandOption { name: "not-tag".to_string(), short_name: Some("T".to_string()), ..Default::default() },
+            ])
+            .build()
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn parse(argument_type: ArgumentType, env_args: &[&str]) -> Result<ParsedArgs> {
+        collect_arguments(args(env_args), &command(argument_type)).map(ParsedArgs::new)
+    }
+
+    fn words(parsed_args: &ParsedArgs) -> Vec<String> {
+        parsed_args.get_many("words").unwrap()
+    }
+
+    #[test]
+    fn reads_negated_words_of_a_query_as_positional() {
+        let parsed_args = parse(ArgumentType::Query, &["milk", "-tag:x", "-oat", "-Test", "-t", "work"]).unwrap();
+        assert_eq!(words(&parsed_args), ["milk", "-tag:x", "-oat", "-Test"]);
+        assert_eq!(parsed_args.get_one::<String>("tag").unwrap().as_deref(), Some("work"));
+        assert!(!parsed_args.flag("not-tag"));
+    }
+
+    #[test]
+    fn reads_attached_values_and_grouped_flags() {
+        let parsed_args = parse(ArgumentType::Text, &["-Tvalue", "-it", "work", "words"]).unwrap();
+        assert_eq!(parsed_args.get_one::<String>("not-tag").unwrap().as_deref(), Some("value"));
+        assert_eq!(parsed_args.get_one::<String>("tag").unwrap().as_deref(), Some("work"));
+        assert!(parsed_args.flag("interactive"));
+        assert_eq!(words(&parsed_args), ["words"]);
+
+        let error = parse(ArgumentType::Text, &["-oat"]).unwrap_err().to_string();
+        assert!(error.starts_with("Unknown option '-o'"), "{error}");
+    }
+
+    #[test]
+    fn reads_long_options_with_an_equals_sign() {
+        let parsed_args = parse(ArgumentType::Text, &["--tag=a=b", "--not-tag", "c"]).unwrap();
+        assert_eq!(parsed_args.get_one::<String>("tag").unwrap().as_deref(), Some("a=b"));
+        assert_eq!(parsed_args.get_one::<String>("not-tag").unwrap().as_deref(), Some("c"));
+        assert!(parse(ArgumentType::Text, &["--interactive=yes"]).is_err());
+    }
+
+    #[test]
+    fn reads_everything_after_double_dash_as_positional() {
+        let parsed_args = parse(ArgumentType::Text, &["-i", "--", "-t", "--tag", "--"]).unwrap();
+        assert!(parsed_args.flag("interactive"));
+        assert!(!parsed_args.flag("tag"));
+        assert_eq!(words(&parsed_args), ["-t", "--tag", "--"]);
+    }
+
+    #[test]
+    fn finds_help_only_among_options() {
+        let command = command(ArgumentType::Text);
+        let is_help = |env_args: &[&str]| search_for_flag(&args(env_args), &command, &["--help", "-h"]);
+        assert!(is_help(&["words", "--help"]));
+        assert!(is_help(&["-i", "-h"]));
+        assert!(!is_help(&["--", "--help", "mode"]));
+        assert!(!is_help(&["-t", "--help"]));
+        assert!(!is_help(&["--tag", "-h"]));
+    }
 }
@@ -0,0 +1,345 @@
+use crate::cli_command::{ArgumentType, CliCommandArgument, CliCommandBuilder, CliCommand, CliCommandOption, ParsedArgs};
+use crate::note_commands::{note_label, open_store_with_config};
+use rustic_notes::{config, RusticError};
+use std::path::Path;
+
+pub fn build_completions_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("completions")
+        .set_description("Print a completion script for bash, zsh or fish, e.g. 'source <(rn completions bash)'")
+        .add_argument(
+            &CliCommandArgument {
+                name: "shell".to_string(),
+                description: Some("Shell to complete in".to_string()),
+                value_type: ArgumentType::Choice(["bash", "zsh", "fish"].map(String::from).to_vec()),
+                is_required: false,
+                is_variadic: false
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "note-ids".to_string(),
+                short_name: None,
+                description: Some("Print the id and title of every note, used by the completion scripts".to_string()),
+                is_flag: true,
+                ..Default::default()
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "tags".to_string(),
+                short_name: None,
+                description: Some("Print every tag, used by the completion scripts".to_string()),
+                is_flag: true,
+                ..Default::default()
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            // these run on every TAB press, so they must not create a config where there is none
+            if args.flag("note-ids") || args.flag("tags") {
+                let Some(config) = config::find_config()? else {
+                    return Ok(());
+                };
+                let store = open_store_with_config(&config, &args)?;
+                if args.flag("note-ids") {
+                    for note in store.list()? {
+                        println!("{}\t{}", note.id, note_label(&note));
+                    }
+                } else {
+                    for tag in store.tags()?.keys() {
+                        println!("{tag}");
+                    }
+                }
+                return Ok(());
+            }
+
+            let Some(shell) = args.get_one::<String>("shell")? else {
+                return Err(RusticError::Usage("Missing argument [shell] for 'completions'.".to_string()));
+            };
+            // complete the name the program was run by, so it works for aliases and renamed binaries too
+            let program = std::env::args().next()
+                .and_then(|program| Path::new(&program).file_name().map(|name| name.to_string_lossy().to_string()))
+                .unwrap_or_else(|| "rn".to_string());
+            let tables = CompletionTables::build(&crate::build_cli());
+            match shell.as_str() {
+                "bash" => print!("{}", bash_script(&program, &tables)),
+                "zsh" => print!("{}", zsh_script(&program, &tables)),
+                _ => print!("{}", fish_script(&program, &tables)),
+            }
+            Ok(())
+        }).build()
+}
+
+// what a value can be completed with, besides a fixed list of words
+const NOTE_IDS: &str = "@ids";
+const TAGS: &str = "@tags";
+const FILES: &str = "@files";
+
+// one `case` per lookup the scripts make, keyed by the path of subcommand names like `tag add`
+#[derive(Default)]
+struct CompletionTables {
+    // `path:word` to the path of the subcommand the word names
+    subcommand: Vec<(String, String)>,
+    subcommands: Vec<(String, String)>,
+    options: Vec<(String, String)>,
+    value_options: Vec<(String, String)>,
+    // `path:option` to what its value completes to
+    option_values: Vec<(String, String)>,
+    // `path:index` of a positional argument to what it completes to, variadic arguments end in `*`
+    argument_values: Vec<(String, String)>,
+}
+
+impl CompletionTables {
+    fn build(root: &CliCommand) -> CompletionTables {
+        let mut tables = CompletionTables::default();
+        tables.add_command(root, "");
+        tables
+    }
+
+    fn add_command(&mut self, command: &CliCommand, path: &str) {
+        let mut subcommand_names = vec![];
+        for subcommand in &command.subcommands {
+            let subcommand_path = if path.is_empty() { subcommand.name.clone() } else { format!("{path} {}", subcommand.name) };
+            for name in std::iter::once(&subcommand.name).chain(&subcommand.aliases) {
+                self.subcommand.push((format!("{path}:{name}"), subcommand_path.clone()));
+            }
+            subcommand_names.push(subcommand.name.clone());
+            self.add_command(subcommand, &subcommand_path);
+        }
+        if !subcommand_names.is_empty() {
+            self.subcommands.push((path.to_string(), subcommand_names.join(" ")));
+        }
+
+        let mut option_names = vec![];
+        let mut value_option_names = vec![];
+        for option in &command.options {
+            let names: Vec<String> = std::iter::once(format!("--{}", option.name))
+                .chain(option.short_name.iter().map(|short_name| format!("-{short_name}")))
+                .collect();
+            if !option.is_flag {
+                value_option_names.extend(names.iter().cloned());
+                if let Some(values) = value_completion(&option.name, &option.value_type) {
+                    self.option_values.extend(names.iter().map(|name| (format!("{path}:{name}"), values.clone())));
+                }
+            }
+            option_names.extend(names);
+        }
+        option_names.push("--help".to_string());
+        self.options.push((path.to_string(), option_names.join(" ")));
+        if !value_option_names.is_empty() {
+            self.value_options.push((path.to_string(), value_option_names.join(" ")));
+        }
+
+        for (index, argument) in command.arguments.iter().enumerate() {
+            if let Some(values) = value_completion(&argument.name, &argument.value_type) {
+                let key = if argument.is_variadic { format!("{path}:*") } else { format!("{path}:{index}") };
+                self.argument_values.push((key, values));
+            }
+        }
+    }
+}
+
+fn value_completion(name: &str, value_type: &ArgumentType) -> Option<String> {
+    match (name, value_type) {
+        (_, ArgumentType::Choice(choices)) => Some(choices.join(" ")),
+        (_, ArgumentType::Path) => Some(FILES.to_string()),
+        ("id", _) => Some(NOTE_IDS.to_string()),
+        ("tag" | "tags" | "not-tag", _) => Some(TAGS.to_string()),
+        _ => None,
+    }
+}
+
+fn function_name(program: &str) -> String {
+    format!("_{}", program.replace(|c: char| !c.is_ascii_alphanumeric(), "_"))
+}
+
+// a `case` over the arguments of a sh function, `*` at the end of a key matches anything
+fn sh_function(name: &str, subject: &str, rows: &[(String, String)]) -> String {
+    let mut function = format!("{name}() {{\n    case \"{subject}\" in\n");
+    for (key, value) in rows {
+        let pattern = match key.strip_suffix('*') {
+            Some(prefix) => format!("'{prefix}'*"),
+            None => format!("'{key}'"),
+        };
+        function.push_str(&format!("        {pattern}) echo '{value}' ;;\n"));
+    }
+    function.push_str("    esac\n}\n\n");
+    function
+}
+
+fn fish_function(name: &str, subject: &str, rows: &[(String, String)]) -> String {
+    let mut function = format!("function {name}\n    switch \"{subject}\"\n");
+    for (key, value) in rows {
+        function.push_str(&format!("        case '{key}'\n            echo '{value}'\n"));
+    }
+    function.push_str("    end\nend\n\n");
+    function
+}
+
+// lookups and the walk over the words before the cursor, shared by bash and zsh
+fn sh_functions(program: &str, tables: &CompletionTables) -> String {
+    let name = function_name(program);
+    let mut script = String::new();
+    script.push_str(&sh_function(&format!("{name}_subcommand"), "$1:$2", &tables.subcommand));
+    script.push_str(&sh_function(&format!("{name}_subcommands"), "$1", &tables.subcommands));
+    script.push_str(&sh_function(&format!("{name}_options"), "$1", &tables.options));
+    script.push_str(&sh_function(&format!("{name}_value_options"), "$1", &tables.value_options));
+    script.push_str(&sh_function(&format!("{name}_option_values"), "$1:$2", &tables.option_values));
+    script.push_str(&sh_function(&format!("{name}_argument_values"), "$1:$2", &tables.argument_values));
+    script.push_str(&format!(r#"# prints what the current word, the first argument, completes to after the words that follow it
+{name}_spec() {{
+    local current=$1 command_path="" value_option="" positional=0 word next subcommands
+    shift
+    for word in "$@"; do
+        if [[ -n $value_option ]]; then
+            value_option=""
+            continue
+        fi
+        if [[ $word == -* ]]; then
+            if [[ $word != *=* && " $({name}_value_options "$command_path") " == *" $word "* ]]; then
+                value_option=$word
+            fi
+            continue
+        fi
+        next=$({name}_subcommand "$command_path" "$word")
+        if [[ -n $next ]]; then
+            command_path=$next
+        else
+            positional=$((positional + 1))
+        fi
+    done
+
+    if [[ -n $value_option ]]; then
+        {name}_option_values "$command_path" "$value_option"
+    elif [[ $current == -* ]]; then
+        {name}_options "$command_path"
+    else
+        subcommands=$({name}_subcommands "$command_path")
+        if [[ -n $subcommands ]]; then
+            echo "$subcommands"
+        else
+            {name}_argument_values "$command_path" "$positional"
+        fi
+    fi
+}}
+
+"#));
+    script
+}
+
+fn bash_script(program: &str, tables: &CompletionTables) -> String {
+    let name = function_name(program);
+    let mut script = format!("# bash completion for {program}\n\n");
+    script.push_str(&sh_functions(program, tables));
+    script.push_str(&format!(r#"{name}() {{
+    local current=${{COMP_WORDS[COMP_CWORD]}} spec
+    spec=$({name}_spec "$current" "${{COMP_WORDS[@]:1:COMP_CWORD-1}}")
+    case $spec in
+        {NOTE_IDS}) COMPREPLY=($(compgen -W "$("${{COMP_WORDS[0]}}" completions --note-ids 2>/dev/null | cut -f1)" -- "$current")) ;;
+        {TAGS}) COMPREPLY=($(compgen -W "$("${{COMP_WORDS[0]}}" completions --tags 2>/dev/null)" -- "$current")) ;;
+        {FILES}) COMPREPLY=($(compgen -f -- "$current")) ;;
+        *) COMPREPLY=($(compgen -W "$spec" -- "$current")) ;;
+    esac
+}}
+
+complete -F {name} {program}
+"#));
+    script
+}
+
+fn zsh_script(program: &str, tables: &CompletionTables) -> String {
+    let name = function_name(program);
+    let mut script = format!("#compdef {program}\n\n");
+    script.push_str(&sh_functions(program, tables));
+    script.push_str(&format!(r#"{name}() {{
+    local spec
+    local -a values
+    spec=$({name}_spec "${{words[CURRENT]}}" "${{(@)words[2,CURRENT-1]}}")
+    case $spec in
+        {NOTE_IDS})
+            values=(${{(f)"$("${{words[1]}}" completions --note-ids 2>/dev/null)"}})
+            values=(${{values//:/\\:}})
+            values=(${{values/$'\t'/:}})
+            _describe 'note' values ;;
+        {TAGS})
+            values=(${{(f)"$("${{words[1]}}" completions --tags 2>/dev/null)"}})
+            compadd -a values ;;
+        {FILES}) _files ;;
+        *)
+            values=(${{=spec}})
+            compadd -a values ;;
+    esac
+}}
+
+if [[ $zsh_eval_context[-1] == loadautofunc ]]; then
+    {name} "$@"
+else
+    compdef {name} {program}
+fi
+"#));
+    script
+}
+
+fn fish_script(program: &str, tables: &CompletionTables) -> String {
+    let name = format!("_{}", function_name(program));
+    let mut script = format!("# fish completion for {program}\n\n");
+    script.push_str(&fish_function(&format!("{name}_subcommand"), "$argv[1]:$argv[2]", &tables.subcommand));
+    script.push_str(&fish_function(&format!("{name}_subcommands"), "$argv[1]", &tables.subcommands));
+    script.push_str(&fish_function(&format!("{name}_options"), "$argv[1]", &tables.options));
+    script.push_str(&fish_function(&format!("{name}_value_options"), "$argv[1]", &tables.value_options));
+    script.push_str(&fish_function(&format!("{name}_option_values"), "$argv[1]:$argv[2]", &tables.option_values));
+    script.push_str(&fish_function(&format!("{name}_argument_values"), "$argv[1]:$argv[2]", &tables.argument_values));
+    script.push_str(&format!(r#"function {name}_complete
+    set -l words (commandline -opc)
+    set -l current (commandline -ct)
+    set -l program $words[1]
+    set -e words[1]
+    set -l command_path ''
+    set -l value_option ''
+    set -l positional 0
+    for word in $words
+        if test -n "$value_option"
+            set value_option ''
+            continue
+        end
+        if string match -q -- '-*' $word
+            if not string match -q -- '*=*' $word; and contains -- $word (string split ' ' -- ({name}_value_options $command_path))
+                set value_option $word
+            end
+            continue
+        end
+        set -l next ({name}_subcommand $command_path $word)
+        if test -n "$next"
+            set command_path $next
+        else
+            set positional (math $positional + 1)
+        end
+    end
+
+    set -l spec
+    if test -n "$value_option"
+        set spec ({name}_option_values $command_path $value_option)
+    else if string match -q -- '-*' $current
+        set spec ({name}_options $command_path)
+    else
+        set spec ({name}_subcommands $command_path)
+        test -n "$spec"; or set spec ({name}_argument_values $command_path $positional)
+    end
+
+    switch "$spec"
+        case ''
+        case '{NOTE_IDS}'
+            $program completions --note-ids 2>/dev/null
+        case '{TAGS}'
+            $program completions --tags 2>/dev/null
+        case '{FILES}'
+            __fish_complete_path $current
+        case '*'
+            string split ' ' -- $spec
+    end
+end
+
+complete -c {program} -f -a '({name}_complete)'
+"#));
+    script
+}
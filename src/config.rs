@@ -1,27 +1,50 @@
 use serde::{Serialize, Deserialize};
+use crate::error::{Result, RusticError};
 
 #[derive(Serialize, Deserialize)]
 pub struct RusticConfig {
     pub notes_directory: String,
     pub editor: Option<String>,
+    #[serde(default = "default_note_extension")]
+    pub note_extension: String,
+    #[serde(default = "default_backup_count")]
+    pub backup_count: usize,
 }
 
-fn default_config() -> RusticConfig {
-    println!("Creating a default configuration file.");
+fn default_note_extension() -> String {
+    "md".to_string()
+}
+
+fn default_backup_count() -> usize {
+    5
+}
+
+fn default_config() -> Result<RusticConfig> {
     let default_config = RusticConfig {
         notes_directory: ".".to_string(),
         editor: None,
+        note_extension: default_note_extension(),
+        backup_count: default_backup_count(),
     };
 
-    let toml_string = toml::to_string_pretty(&default_config).unwrap();
-    std::fs::write("config.toml", toml_string).unwrap();
-    default_config
+    let toml_string = toml::to_string_pretty(&default_config).map_err(|e| RusticError::Config(e.to_string()))?;
+    std::fs::write("config.toml", toml_string)?;
+    Ok(default_config)
+}
+
+pub fn get_config() -> Result<RusticConfig> {
+    match find_config()? {
+        Some(config) => Ok(config),
+        None => default_config(),
+    }
 }
 
-pub fn get_config() -> RusticConfig {
+// the config if there is one, without creating a default one
+pub fn find_config() -> Result<Option<RusticConfig>> {
     match std::fs::read_to_string("config.toml") {
-        Ok(data) => toml::from_str(&data).unwrap_or_else(|_| default_config()),
-        Err(_) => default_config(),
+        Ok(data) => toml::from_str(&data).map(Some).map_err(|e| RusticError::Config(format!("config.toml: {e}"))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -0,0 +1,131 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edit<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Line based diff of two texts in the unified format, with `context` unchanged lines around every
+/// change. Returns an empty string when both texts are the same.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str, context: usize) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = diff_lines(&old_lines, &new_lines);
+
+    let changes: Vec<usize> = edits.iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // changes closer to each other than twice the context end up in the same hunk
+    let mut hunks: Vec<(usize, usize)> = vec![];
+    for &change in &changes {
+        let start = change.saturating_sub(context);
+        let end = (change + context + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some(last_hunk) if start <= last_hunk.1 => last_hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut output = format!("--- {old_label}\n+++ {new_label}\n");
+    for (start, end) in hunks {
+        let old_before = edits[..start].iter().filter(|edit| !matches!(edit, Edit::Insert(_))).count();
+        let new_before = edits[..start].iter().filter(|edit| !matches!(edit, Edit::Delete(_))).count();
+        let old_count = edits[start..end].iter().filter(|edit| !matches!(edit, Edit::Insert(_))).count();
+        let new_count = edits[start..end].iter().filter(|edit| !matches!(edit, Edit::Delete(_))).count();
+
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_before, old_count),
+            hunk_range(new_before, new_count)
+        ));
+        for edit in &edits[start..end] {
+            match edit {
+                Edit::Equal(line) => output.push_str(&format!(" {line}\n")),
+                Edit::Delete(line) => output.push_str(&format!("-{line}\n")),
+                Edit::Insert(line) => output.push_str(&format!("+{line}\n")),
+            }
+        }
+    }
+    output
+}
+
+// empty ranges point at the line before them, as in GNU diff
+fn hunk_range(lines_before: usize, count: usize) -> String {
+    match count {
+        0 => format!("{lines_before},0"),
+        1 => format!("{}", lines_before + 1),
+        _ => format!("{},{count}", lines_before + 1),
+    }
+}
+
+// longest common subsequence, notes are short enough for the quadratic table
+fn diff_lines<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> Vec<Edit<'a>> {
+    let mut lengths = vec![vec![0u32; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev() {
+        for j in (0..new_lines.len()).rev() {
+            lengths[i][j] = if old_lines[i] == new_lines[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() && j < new_lines.len() {
+        if old_lines[i] == new_lines[j] {
+            edits.push(Edit::Equal(old_lines[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            edits.push(Edit::Delete(old_lines[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(new_lines[j]));
+            j += 1;
+        }
+    }
+    edits.extend(old_lines[i..].iter().map(|line| Edit::Delete(line)));
+    edits.extend(new_lines[j..].iter().map(|line| Edit::Insert(line)));
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_nothing_for_equal_texts() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new", 3), "");
+    }
+
+    #[test]
+    fn shows_changed_lines_with_context() {
+        assert_eq!(
+            unified_diff("a\nb\nc\n", "a\nB\nc\n", "old", "new", 1),
+            "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n"
+        );
+    }
+
+    #[test]
+    fn splits_distant_changes_into_hunks() {
+        let old: String = (1..=10).map(|line| format!("{line}\n")).collect();
+        let new = old.replace("2\n", "two\n").replace("9\n", "nine\n");
+        assert_eq!(
+            unified_diff(&old, &new, "old", "new", 1),
+            "--- old\n+++ new\n@@ -1,3 +1,3 @@\n 1\n-2\n+two\n 3\n@@ -8,3 +8,3 @@\n 8\n-9\n+nine\n 10\n"
+        );
+    }
+
+    #[test]
+    fn points_empty_ranges_at_the_line_before() {
+        assert_eq!(unified_diff("", "a\n", "old", "new", 3), "--- old\n+++ new\n@@ -0,0 +1 @@\n+a\n");
+    }
+}
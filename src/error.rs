@@ -0,0 +1,61 @@
+use std::{fmt, io};
+
+pub type Result<T> = std::result::Result<T, RusticError>;
+
+#[derive(Debug)]
+pub enum RusticError {
+    Io(io::Error),
+    Parse(String),
+    NotFound(String),
+    Editor(String),
+    Config(String),
+    Usage(String),
+}
+
+impl RusticError {
+    // exit codes follow sysexits.h so scripts can tell the error classes apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RusticError::Usage(_) => 64,
+            RusticError::Parse(_) => 65,
+            RusticError::NotFound(_) => 66,
+            RusticError::Editor(_) => 69,
+            RusticError::Io(_) => 74,
+            RusticError::Config(_) => 78,
+        }
+    }
+}
+
+impl fmt::Display for RusticError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RusticError::Io(e) => write!(f, "{e}"),
+            RusticError::Parse(message) => write!(f, "{message}"),
+            RusticError::NotFound(what) => write!(f, "{what} not found."),
+            RusticError::Editor(message) => write!(f, "{message}"),
+            RusticError::Config(message) => write!(f, "Invalid configuration: {message}"),
+            RusticError::Usage(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for RusticError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RusticError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RusticError {
+    fn from(e: io::Error) -> Self {
+        RusticError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for RusticError {
+    fn from(e: serde_json::Error) -> Self {
+        RusticError::Parse(e.to_string())
+    }
+}
@@ -0,0 +1,124 @@
+use crate::cli_command::{ArgumentType, CliCommandArgument, CliCommandBuilder, CliCommand, CliCommandOption, ParsedArgs};
+use crate::note_commands::{get_query_argument, open_store, parse_tag_filter, search_notes, tag_filter_options};
+use crate::print_utils;
+use rustic_notes::{export::{self, ExportFormat}, import, RusticError};
+use std::path::Path;
+
+pub fn build_import_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("import")
+        .set_description("Import a directory of Markdown and plain text files, one note per file")
+        .add_argument(
+            &CliCommandArgument {
+                name: "path".to_string(),
+                description: Some("Directory to import the files of".to_string()),
+                value_type: ArgumentType::Path,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let path: String = args.get_required("path")?;
+            if !Path::new(&path).is_dir() {
+                return Err(RusticError::NotFound(format!("Directory '{path}'")));
+            }
+
+            let summary = import::import_directory(&open_store(&args)?, Path::new(&path))?;
+            for note in &summary.imported {
+                println!("{}. {}", note.id, note.title().unwrap_or_default());
+            }
+            for (paths, reason) in [(&summary.duplicates, "duplicate"), (&summary.empty, "empty"), (&summary.unreadable, "not UTF-8")] {
+                for skipped_path in paths {
+                    println!("{}", print_utils::colorize(print_utils::Color::warning(), format!("Skipped {} ({reason}).", skipped_path.display()).as_str()));
+                }
+            }
+
+            let skipped_count = summary.duplicates.len() + summary.empty.len() + summary.unreadable.len();
+            println!(
+                "{}",
+                print_utils::colorize(
+                    print_utils::Color::success(),
+                    format!("Imported {} notes, skipped {skipped_count} files ({} duplicates).", summary.imported.len(), summary.duplicates.len()).as_str()
+                )
+            );
+            Ok(())
+        }).build()
+}
+
+pub fn build_export_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("export")
+        .set_description("Export notes, or only those matching a search query, to share them outside of the tool")
+        .add_argument(
+            &CliCommandArgument {
+                name: "query".to_string(),
+                description: Some("Only export notes matching this query".to_string()),
+                value_type: ArgumentType::Query,
+                is_required: false,
+                is_variadic: true
+            }
+        )
+        .add_options(&tag_filter_options())
+        .add_option(
+            &CliCommandOption {
+                name: "format".to_string(),
+                short_name: Some("f".to_string()),
+                description: Some("Export as Markdown, JSON, CSV or HTML".to_string()),
+                is_flag: false,
+                default_value: Some("md".to_string()),
+                value_type: ArgumentType::Choice(["md", "markdown", "json", "csv", "html"].map(String::from).to_vec()),
+                ..Default::default()
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "output".to_string(),
+                short_name: Some("o".to_string()),
+                description: Some("Write all notes to this file instead of printing them".to_string()),
+                is_flag: false,
+                ..Default::default()
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "directory".to_string(),
+                short_name: Some("d".to_string()),
+                description: Some("Write every note to a file of its own in this directory".to_string()),
+                is_flag: false,
+                ..Default::default()
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let format = ExportFormat::parse(&args.get_required::<String>("format")?).unwrap_or(ExportFormat::Markdown);
+            let output: Option<String> = args.get_one("output")?;
+            let directory: Option<String> = args.get_one("directory")?;
+            if output.is_some() && directory.is_some() {
+                return Err(RusticError::Usage("--output and --directory cannot be combined.".to_string()));
+            }
+
+            let store = open_store(&args)?;
+            let query = get_query_argument(&args, "query")?;
+            let mut notes = search_notes(store.list()?, &store.search_index()?, query.as_deref(), &parse_tag_filter(&args)?)?;
+            notes.sort_by_key(|note| note.id);
+            if notes.is_empty() {
+                println!("{}", print_utils::colorize(print_utils::Color::warning(), "No notes found."));
+                return Ok(());
+            }
+
+            match (output, directory) {
+                (_, Some(directory)) => {
+                    std::fs::create_dir_all(&directory)?;
+                    for note in &notes {
+                        std::fs::write(Path::new(&directory).join(export::note_file_name(note, format)), export::export_note(note, format))?;
+                    }
+                    println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Exported {} notes to {directory}.", notes.len()).as_str()));
+                },
+                (Some(output), None) => {
+                    std::fs::write(&output, export::export_notes(&notes, format))?;
+                    println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Exported {} notes to {output}.", notes.len()).as_str()));
+                },
+                (None, None) => print!("{}", export::export_notes(&notes, format)),
+            }
+            Ok(())
+        }).build()
+}
@@ -0,0 +1,172 @@
+use serde::Serialize;
+use crate::notes::RusticNote;
+use crate::time_utils;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Csv,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Option<ExportFormat> {
+        match name {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            "html" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+// what gets exported of a note, with the title already resolved
+#[derive(Serialize)]
+struct ExportedNote<'a> {
+    id: u32,
+    title: Option<String>,
+    tags: &'a [String],
+    created_at: u64,
+    updated_at: u64,
+    content: &'a str,
+}
+
+impl<'a> ExportedNote<'a> {
+    fn from(note: &'a RusticNote) -> Self {
+        ExportedNote {
+            id: note.id,
+            title: note.title(),
+            tags: &note.tags,
+            created_at: note.created_at,
+            updated_at: note.updated_at,
+            content: &note.content,
+        }
+    }
+}
+
+// every note in a single document
+pub fn export_notes(notes: &[RusticNote], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => notes.iter().map(markdown_section).collect::<Vec<_>>().join("\n"),
+        ExportFormat::Json => {
+            let exported_notes: Vec<ExportedNote> = notes.iter().map(ExportedNote::from).collect();
+            serde_json::to_string_pretty(&exported_notes).unwrap_or_default() + "\n"
+        },
+        ExportFormat::Csv => csv_header() + &notes.iter().map(csv_row).collect::<String>(),
+        ExportFormat::Html => html_document("Notes", &notes.iter().map(html_article).collect::<String>()),
+    }
+}
+
+// a document of its own for a single note, markdown notes keep their metadata in front matter so
+// they can be imported again
+pub fn export_note(note: &RusticNote, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Markdown => {
+            let mut front_matter = String::from("---\n");
+            if let Some(title) = &note.title {
+                front_matter.push_str(&format!("title: {title}\n"));
+            }
+            if !note.tags.is_empty() {
+                front_matter.push_str(&format!("tags: [{}]\n", note.tags.join(", ")));
+            }
+            front_matter.push_str(&format!("created: {}\nupdated: {}\n---\n", time_utils::format_timestamp(note.created_at), time_utils::format_timestamp(note.updated_at)));
+            format!("{front_matter}{}\n", note.content)
+        },
+        ExportFormat::Json => serde_json::to_string_pretty(&ExportedNote::from(note)).unwrap_or_default() + "\n",
+        ExportFormat::Csv => csv_header() + &csv_row(note),
+        ExportFormat::Html => html_document(&note_title(note), &html_article(note)),
+    }
+}
+
+// `<id>-<title>.<extension>`, with the title reduced to lowercase letters, digits and dashes
+pub fn note_file_name(note: &RusticNote, format: ExportFormat) -> String {
+    let slug: String = note_title(note).to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(40)
+        .collect();
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        format!("{}.{}", note.id, format.extension())
+    } else {
+        format!("{}-{slug}.{}", note.id, format.extension())
+    }
+}
+
+fn note_title(note: &RusticNote) -> String {
+    note.title().unwrap_or_else(|| note.content.lines().next().unwrap_or_default().to_string())
+}
+
+fn metadata_line(note: &RusticNote) -> String {
+    let mut parts = vec![format!("#{}", note.id)];
+    if !note.tags.is_empty() {
+        parts.push(format!("tags: {}", note.tags.join(", ")));
+    }
+    parts.push(format!("created {}", time_utils::format_timestamp(note.created_at)));
+    parts.push(format!("updated {}", time_utils::format_timestamp(note.updated_at)));
+    parts.join(" · ")
+}
+
+fn markdown_section(note: &RusticNote) -> String {
+    format!("## {}\n\n*{}*\n\n{}\n", note_title(note), metadata_line(note), note.content)
+}
+
+fn csv_header() -> String {
+    String::from("id,title,tags,created_at,updated_at,content\r\n")
+}
+
+fn csv_row(note: &RusticNote) -> String {
+    let fields = [
+        note.id.to_string(),
+        note.title().unwrap_or_default(),
+        note.tags.join(";"),
+        time_utils::format_timestamp(note.created_at),
+        time_utils::format_timestamp(note.updated_at),
+        note.content.clone(),
+    ];
+    fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",") + "\r\n"
+}
+
+// quoted as in RFC 4180 whenever the field has anything that would break the row apart
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn html_article(note: &RusticNote) -> String {
+    format!(
+        "<article id=\"note-{}\">\n<h2>{}</h2>\n<p class=\"metadata\">{}</p>\n<pre>{}</pre>\n</article>\n",
+        note.id,
+        escape_html(&note_title(note)),
+        escape_html(&metadata_line(note)),
+        escape_html(&note.content)
+    )
+}
+
+fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>pre {{ white-space: pre-wrap; }} .metadata {{ color: gray; }}</style>\n</head>\n<body>\n{body}</body>\n</html>\n",
+        escape_html(title)
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,204 @@
+use std::ops::Range;
+use crate::search;
+
+// scores follow fzf: every matched character is worth the same, matches at the start of a word or
+// right after another match are worth more, and gaps between matches cost a little per character
+const SCORE_MATCH: i64 = 16;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+const PENALTY_TYPO: i64 = 24;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    // byte ranges of the matched characters, sorted and not overlapping
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Matches every whitespace separated word of the pattern against the text, case-insensitive.
+///
+/// A word matches when its characters appear in the text in the same order, as in fzf, or when a
+/// word of the text is only a typo or two away from it, e.g. `delpoy` finds `deploy`. The better
+/// scoring of the two is used.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let words: Vec<&str> = pattern.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let text_chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut score = 0;
+    let mut positions = vec![];
+    for word in words {
+        let (word_score, word_positions) = [subsequence_match(word, &text_chars), typo_match(word, text)].into_iter()
+            .flatten()
+            .max_by_key(|(score, _)| *score)?;
+        score += word_score;
+        positions.extend(word_positions);
+    }
+
+    positions.sort();
+    positions.dedup();
+    Some(FuzzyMatch { score, ranges: merge_positions(text, &positions) })
+}
+
+// best scoring placement of the word as a subsequence of the text, with the byte offsets of its characters
+fn subsequence_match(word: &str, text_chars: &[(usize, char)]) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = word.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+    let lowercase_text: Vec<char> = text_chars.iter().map(|&(_, c)| c.to_lowercase().next().unwrap_or(c)).collect();
+    let (m, n) = (pattern.len(), lowercase_text.len());
+    if m == 0 || m > n {
+        return None;
+    }
+
+    // scores[i][j] is the best score of matching pattern[..=i] with pattern[i] on text[j]
+    let mut scores = vec![vec![None; n]; m];
+    let mut previous = vec![vec![0; n]; m];
+    for i in 0..m {
+        // best of scores[i - 1][k] + k * PENALTY_GAP_EXTENSION over k < j - 1, which is the same k as the
+        // best score after paying for the gap up to j, whatever the extension penalty is
+        let mut best_gap: Option<(i64, usize)> = None;
+        for j in i..n {
+            if i > 0 && j >= 2 && let Some(score) = scores[i - 1][j - 2] {
+                let candidate = score + (j - 2) as i64 * PENALTY_GAP_EXTENSION;
+                if best_gap.is_none_or(|(best, _)| candidate > best) {
+                    best_gap = Some((candidate, j - 2));
+                }
+            }
+            if lowercase_text[j] != pattern[i] {
+                continue;
+            }
+
+            let bonus = boundary_bonus(text_chars, j);
+            if i == 0 {
+                scores[i][j] = Some(SCORE_MATCH + bonus * BONUS_FIRST_CHAR_MULTIPLIER);
+                continue;
+            }
+
+            let consecutive = scores[i - 1][j - 1].map(|score| (score + SCORE_MATCH + bonus.max(BONUS_CONSECUTIVE), j - 1));
+            let gap = best_gap.map(|(best, k)| {
+                let gap_length = (j - k - 1) as i64;
+                (best - k as i64 * PENALTY_GAP_EXTENSION + SCORE_MATCH + bonus - PENALTY_GAP_START - (gap_length - 1) * PENALTY_GAP_EXTENSION, k)
+            });
+            let best = match (consecutive, gap) {
+                (Some(a), Some(b)) => Some(if a.0 >= b.0 { a } else { b }),
+                (a, b) => a.or(b),
+            };
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score);
+                previous[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = scores[m - 1].iter().enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+    let mut positions = vec![text_chars[j].0];
+    for i in (1..m).rev() {
+        j = previous[i][j];
+        positions.push(text_chars[j].0);
+    }
+    positions.reverse();
+    Some((score, positions))
+}
+
+fn boundary_bonus(text_chars: &[(usize, char)], j: usize) -> i64 {
+    let current = text_chars[j].1;
+    match j.checked_sub(1).map(|k| text_chars[k].1) {
+        None => BONUS_BOUNDARY,
+        Some(before) if !before.is_alphanumeric() && current.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(before) if before.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+// closest word of the text within a small edit distance, longer words tolerate more typos
+fn typo_match(word: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = word.to_lowercase().chars().collect();
+    let allowed_distance = if pattern.len() <= 4 { 1 } else { 2 };
+
+    let (distance, range) = search::tokenize(text).into_iter()
+        .map(|(token, range)| (edit_distance(&pattern, &token.chars().collect::<Vec<_>>()), range))
+        .filter(|&(distance, _)| distance <= allowed_distance)
+        .min_by_key(|(distance, range)| (*distance, range.start))?;
+
+    let score = pattern.len() as i64 * SCORE_MATCH - distance as i64 * PENALTY_TYPO;
+    Some((score, text[range.clone()].char_indices().map(|(offset, _)| range.start + offset).collect()))
+}
+
+// optimal string alignment distance, a swap of two neighbouring characters counts as one edit
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            distances[i][j] = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + substitution_cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distances[i][j] = distances[i][j].min(distances[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+fn merge_positions(text: &str, positions: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for &position in positions {
+        let end = position + text[position..].chars().next().map_or(0, char::len_utf8);
+        match ranges.last_mut() {
+            Some(last) if last.end == position => last.end = end,
+            _ => ranges.push(position..end),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> i64 {
+        fuzzy_match(pattern, text).unwrap().score
+    }
+
+    #[test]
+    fn ranks_consecutive_and_boundary_matches_above_scattered_ones() {
+        assert!(score("deploy", "deploy the app") > score("deploy", "do extra planning on yaml"));
+        assert!(score("nb", "new build") > score("nb", "unbox"));
+        assert_eq!(fuzzy_match("xyz", "deploy"), None);
+    }
+
+    #[test]
+    fn finds_words_with_typos() {
+        let typo = fuzzy_match("delpoy", "deploy to prod").unwrap();
+        assert_eq!(typo.ranges, vec![0..6]);
+        assert_eq!(typo.score, 6 * SCORE_MATCH - PENALTY_TYPO);
+        assert_eq!(fuzzy_match("dxxxoy", "deploy"), None);
+    }
+
+    #[test]
+    fn highlights_byte_ranges_of_non_ascii_text() {
+        assert_eq!(fuzzy_match("CAFÉ", "naïve café").unwrap().ranges, vec![7..12]);
+        assert_eq!(fuzzy_match("ïe", "naïve").unwrap().ranges, vec![2..4, 5..6]);
+    }
+
+    #[test]
+    fn charges_the_gap_start_once_and_every_further_character_of_a_gap() {
+        let one_character_gap = 2 * SCORE_MATCH + BONUS_BOUNDARY - PENALTY_GAP_START;
+        assert_eq!(score("ab", "xa-b"), one_character_gap);
+        assert_eq!(score("ab", "xa--b"), one_character_gap - PENALTY_GAP_EXTENSION);
+        assert_eq!(score("ab", "xa---b"), one_character_gap - 2 * PENALTY_GAP_EXTENSION);
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::{BTreeMap, BTreeSet};
+use serde::Serialize;
+use crate::links::{self, LinkGraph};
+use crate::notes::RusticNote;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Note,
+    Tag,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    // note -> tag it has
+    Tag,
+    // tag -- tag, weighted by the number of notes having both
+    Cooccurrence,
+    // note -> note it mentions with `#<id>` or links to with `[[...]]`
+    Reference,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GraphNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: EdgeKind,
+    pub weight: u32,
+}
+
+/// How notes relate through their tags and references, ready to be rendered with Graphviz or read
+/// as JSON. Nodes are named `note:<id>` and `tag:<tag>`.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct NoteGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl NoteGraph {
+    pub fn build(notes: &[RusticNote]) -> NoteGraph {
+        let mut graph = NoteGraph::default();
+        let ids: BTreeSet<u32> = notes.iter().map(|note| note.id).collect();
+        let link_graph = LinkGraph::build(notes);
+        let mut tags: BTreeSet<&str> = BTreeSet::new();
+        let mut cooccurrences: BTreeMap<(&str, &str), u32> = BTreeMap::new();
+
+        for note in notes {
+            let label = note.title().unwrap_or_else(|| note.content.lines().next().unwrap_or_default().to_string());
+            graph.nodes.push(GraphNode { id: note_node(note.id), kind: NodeKind::Note, label });
+
+            let mut note_tags: Vec<&str> = note.tags.iter().map(|tag| tag.as_str()).collect();
+            note_tags.sort();
+            note_tags.dedup();
+            for (index, tag) in note_tags.iter().enumerate() {
+                tags.insert(tag);
+                graph.edges.push(GraphEdge { source: note_node(note.id), target: tag_node(tag), kind: EdgeKind::Tag, weight: 1 });
+                for other_tag in &note_tags[index + 1..] {
+                    *cooccurrences.entry((tag, other_tag)).or_insert(0) += 1;
+                }
+            }
+
+            let mut references: BTreeSet<u32> = links::parse_references(&note.content).into_iter()
+                .filter(|id| ids.contains(id))
+                .collect();
+            references.extend(link_graph.links(note.id).iter().filter_map(|link| link.note_id));
+            references.remove(&note.id);
+            for id in references {
+                graph.edges.push(GraphEdge { source: note_node(note.id), target: note_node(id), kind: EdgeKind::Reference, weight: 1 });
+            }
+        }
+
+        for tag in tags {
+            graph.nodes.push(GraphNode { id: tag_node(tag), kind: NodeKind::Tag, label: tag.to_string() });
+        }
+        for ((tag, other_tag), weight) in cooccurrences {
+            graph.edges.push(GraphEdge { source: tag_node(tag), target: tag_node(other_tag), kind: EdgeKind::Cooccurrence, weight });
+        }
+        graph
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut output = String::from("digraph notes {\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Note => "box",
+                NodeKind::Tag => "ellipse",
+            };
+            output.push_str(&format!("    {} [label={}, shape={shape}];\n", quote(&node.id), quote(&node.label)));
+        }
+        for edge in &self.edges {
+            let attributes = match edge.kind {
+                EdgeKind::Tag => String::from(" [style=dashed]"),
+                EdgeKind::Cooccurrence => format!(" [dir=none, label={0}, weight={0}, penwidth={0}]", edge.weight),
+                EdgeKind::Reference => String::new(),
+            };
+            output.push_str(&format!("    {} -> {}{attributes};\n", quote(&edge.source), quote(&edge.target)));
+        }
+        output.push_str("}\n");
+        output
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+fn note_node(id: u32) -> String {
+    format!("note:{id}")
+}
+
+fn tag_node(tag: &str) -> String {
+    format!("tag:{tag}")
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
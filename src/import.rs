@@ -0,0 +1,169 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::error::Result;
+use crate::notes::{NewNote, NoteStore, RusticNote};
+
+const IMPORTED_EXTENSIONS: [&str; 3] = ["md", "markdown", "txt"];
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub imported: Vec<RusticNote>,
+    // files with the same content as an existing note or an earlier file
+    pub duplicates: Vec<PathBuf>,
+    pub empty: Vec<PathBuf>,
+    // files that are not valid UTF-8
+    pub unreadable: Vec<PathBuf>,
+}
+
+/// Imports every Markdown and plain text file under `root` as a note, in a single batch.
+///
+/// Front matter between `---` lines can set the `title` and `tags` of a note. Without a title the
+/// note keeps its leading `# heading` or is named after its file, and without tags it is tagged with
+/// the folders leading to it, e.g. `work/infra/k8s.md` gets the tag `work/infra`.
+pub fn import_directory(store: &NoteStore, root: &Path) -> Result<ImportSummary> {
+    let mut files = vec![];
+    collect_files(root, &mut files)?;
+    files.sort();
+
+    let mut summary = ImportSummary::default();
+    let mut known_contents: HashSet<String> = store.list()?.into_iter().map(|note| note.content.trim().to_string()).collect();
+    let mut new_notes = vec![];
+    for file in files {
+        let text = match std::fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                summary.unreadable.push(file);
+                continue;
+            },
+            Err(e) => return Err(e.into()),
+        };
+
+        let new_note = parse_note_file(file.strip_prefix(root).unwrap_or(&file), &text);
+        if new_note.content.is_empty() {
+            summary.empty.push(file);
+        } else if !known_contents.insert(new_note.content.clone()) {
+            summary.duplicates.push(file);
+        } else {
+            new_notes.push(new_note);
+        }
+    }
+
+    summary.imported = store.create_many(new_notes)?;
+    Ok(summary)
+}
+
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        let is_hidden = path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with('.'));
+        if is_hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.extension().and_then(|e| e.to_str()).is_some_and(|e| IMPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str())) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// `relative_path` is the path of the file inside the imported directory
+fn parse_note_file(relative_path: &Path, text: &str) -> NewNote {
+    let (front_matter, content) = split_front_matter(text);
+    let mut new_note = NewNote { content: content.trim().to_string(), title: None, tags: vec![] };
+
+    for (key, value) in front_matter {
+        match key.as_str() {
+            "title" if !value.is_empty() => new_note.title = Some(value.trim_matches(['"', '\'']).to_string()),
+            "tags" => new_note.tags.extend(value.trim_matches(['[', ']']).split(',').filter_map(tag_from_name)),
+            _ => {},
+        }
+    }
+
+    let has_heading = content.trim_start().lines().next().is_some_and(|line| line.starts_with("# "));
+    if new_note.title.is_none() && !has_heading {
+        new_note.title = relative_path.file_stem().map(|stem| stem.to_string_lossy().to_string());
+    }
+    if new_note.tags.is_empty() {
+        let folders: Vec<String> = relative_path.parent()
+            .map(|parent| parent.iter().filter_map(|folder| tag_from_name(&folder.to_string_lossy())).collect())
+            .unwrap_or_default();
+        if !folders.is_empty() {
+            new_note.tags.push(folders.join("/"));
+        }
+    }
+    new_note
+}
+
+// simple `key: value` front matter, lists can be `[a, b]`, `a, b` or one `- item` per line
+fn split_front_matter(text: &str) -> (Vec<(String, String)>, &str) {
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (vec![], text);
+    };
+    // the closing `---` has to be a line of its own, which can be the very first one
+    let mut offset = 0;
+    let mut closing_line = None;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            closing_line = Some(offset..offset + line.len());
+            break;
+        }
+        offset += line.len();
+    }
+    let Some(closing_line) = closing_line else {
+        return (vec![], text);
+    };
+    let (front_matter, content) = (&rest[..closing_line.start], &rest[closing_line.end..]);
+
+    let mut fields: Vec<(String, String)> = vec![];
+    for line in front_matter.lines() {
+        if let Some(item) = line.trim().strip_prefix("- ") && let Some((_, value)) = fields.last_mut() {
+            if !value.is_empty() {
+                value.push(',');
+            }
+            value.push_str(item.trim());
+        } else if let Some((key, value)) = line.split_once(':') {
+            fields.push((key.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    (fields, content)
+}
+
+// tags cannot contain spaces, so `Meeting notes` becomes `Meeting-notes`
+fn tag_from_name(name: &str) -> Option<String> {
+    let tag = name.trim().trim_matches(['"', '\'']).split_whitespace().collect::<Vec<_>>().join("-");
+    let tag = tag.split('/').filter(|level| !level.is_empty()).collect::<Vec<_>>().join("/");
+    if tag.is_empty() { None } else { Some(tag) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn splits_fields_from_the_content() {
+        let text = "---\ntitle: A\ntags:\n  - x\n  - y\n---\nbody\n";
+        assert_eq!(split_front_matter(text), (fields(&[("title", "A"), ("tags", "x,y")]), "body\n"));
+        assert_eq!(split_front_matter("---\r\ntitle: A\r\n---\r\nbody"), (fields(&[("title", "A")]), "body"));
+        assert_eq!(split_front_matter("no front matter\n---\n"), (vec![], "no front matter\n---\n"));
+    }
+
+    #[test]
+    fn closes_empty_front_matter_on_its_first_line() {
+        assert_eq!(split_front_matter("---\n---\nbody\n---\nmore\n"), (vec![], "body\n---\nmore\n"));
+        assert_eq!(split_front_matter("---\n---\n"), (vec![], ""));
+    }
+
+    #[test]
+    fn only_closes_on_exactly_three_dashes() {
+        assert_eq!(split_front_matter("---\ntitle: A\n---\nintro\n----\nmore"), (fields(&[("title", "A")]), "intro\n----\nmore"));
+        assert_eq!(split_front_matter("---\ntitle: A\n----\nbody"), (vec![], "---\ntitle: A\n----\nbody"));
+    }
+}
@@ -0,0 +1,20 @@
+pub mod config;
+pub mod diff;
+pub mod error;
+pub mod export;
+pub mod fuzzy;
+pub mod graph;
+pub mod import;
+pub mod links;
+pub mod notes;
+pub mod projects;
+pub mod query;
+pub mod search;
+pub mod time_utils;
+mod manifest;
+mod search_log;
+mod storage;
+
+pub use error::{Result, RusticError};
+pub use notes::{NoteStore, RusticNote};
+pub use projects::Workspace;
@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::notes::RusticNote;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LinkTarget {
+    // `[[#42]]`
+    Id(u32),
+    // `[[note title]]`, matched ignoring case
+    Title(String),
+}
+
+impl std::fmt::Display for LinkTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkTarget::Id(id) => write!(f, "[[#{id}]]"),
+            LinkTarget::Title(title) => write!(f, "[[{title}]]"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+    pub target: LinkTarget,
+    // id of the note the link points at, None for broken links
+    pub note_id: Option<u32>,
+}
+
+// links in the order they appear, a link cannot span several lines
+pub fn parse_links(content: &str) -> Vec<LinkTarget> {
+    let mut targets = vec![];
+    for line in content.lines() {
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let Some(length) = rest[start + 2..].find("]]") else {
+                break;
+            };
+            let inner = rest[start + 2..start + 2 + length].trim();
+            rest = &rest[start + 2 + length + 2..];
+
+            let target = match inner.strip_prefix('#').and_then(|id| id.parse::<u32>().ok()) {
+                Some(id) => LinkTarget::Id(id),
+                None if !inner.is_empty() => LinkTarget::Title(inner.to_string()),
+                None => continue,
+            };
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+// bare `#42` references to other notes, which include the ones inside `[[#42]]` links
+pub fn parse_references(content: &str) -> Vec<u32> {
+    let mut ids = vec![];
+    let mut previous = None;
+    for (index, c) in content.char_indices() {
+        let follows_word = previous.is_some_and(|p: char| p.is_alphanumeric() || p == '_' || p == '#');
+        previous = Some(c);
+        if c != '#' || follows_word {
+            continue;
+        }
+
+        let digits: String = content[index + 1..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        let ends_word = content[index + 1 + digits.len()..].chars().next().is_none_or(|c| !c.is_alphanumeric() && c != '_');
+        if ends_word && let Ok(id) = digits.parse::<u32>() {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// Links between notes, resolved against the notes they were built from.
+///
+/// Title links point at the note with that title, the oldest one if several share it.
+#[derive(Clone, Debug, Default)]
+pub struct LinkGraph {
+    outgoing: BTreeMap<u32, Vec<Link>>,
+    incoming: BTreeMap<u32, BTreeSet<u32>>,
+}
+
+impl LinkGraph {
+    pub fn build(notes: &[RusticNote]) -> LinkGraph {
+        let ids: BTreeSet<u32> = notes.iter().map(|note| note.id).collect();
+        let mut titles: BTreeMap<String, u32> = BTreeMap::new();
+        for note in notes {
+            if let Some(title) = note.title() {
+                let id = titles.entry(title.to_lowercase()).or_insert(note.id);
+                *id = (*id).min(note.id);
+            }
+        }
+
+        let mut graph = LinkGraph::default();
+        for note in notes {
+            let links: Vec<Link> = parse_links(&note.content).into_iter()
+                .map(|target| {
+                    let note_id = match &target {
+                        LinkTarget::Id(id) => ids.contains(id).then_some(*id),
+                        LinkTarget::Title(title) => titles.get(&title.to_lowercase()).copied(),
+                    };
+                    Link { target, note_id }
+                })
+                .collect();
+
+            for link_id in links.iter().filter_map(|link| link.note_id) {
+                graph.incoming.entry(link_id).or_default().insert(note.id);
+            }
+            graph.outgoing.insert(note.id, links);
+        }
+        graph
+    }
+
+    pub fn links(&self, id: u32) -> &[Link] {
+        self.outgoing.get(&id).map_or(&[], |links| links.as_slice())
+    }
+
+    // ids of the notes linking to this one
+    pub fn backlinks(&self, id: u32) -> Vec<u32> {
+        self.incoming.get(&id).map_or(vec![], |ids| ids.iter().copied().collect())
+    }
+
+    // every link that points nowhere, with the id of the note it is in
+    pub fn broken_links(&self) -> Vec<(u32, &Link)> {
+        self.outgoing.iter()
+            .flat_map(|(&id, links)| links.iter().filter(|link| link.note_id.is_none()).map(move |link| (id, link)))
+            .collect()
+    }
+}
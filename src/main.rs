@@ -1,35 +1,61 @@
 mod cli_command;
-mod notes;
+mod completion_commands;
+mod exchange_commands;
 mod note_commands;
+mod project_commands;
+mod tag_commands;
 mod print_utils;
-mod config;
+mod tui;
 
 use std::env;
-use cli_command::{CliCommandBuilder, CliCommand};
+use cli_command::{CliCommandBuilder, CliCommand, CliCommandOption};
 
 const ROOT_VERSION: &str = "0.1.0";
 
 fn main() {
-    // todo #945 add variadic positional argument
     // todo #946 add option to builder, to let help not be action taken if no command is not specified and instead print error
-    let cli: CliCommand = CliCommandBuilder::default()
+    let cli = build_cli();
+    if let Err(e) = cli.run(env::args()) {
+        eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error: {e}").as_str()));
+        std::process::exit(e.exit_code());
+    }
+}
+
+// the whole command tree, completion scripts are generated from it as well
+fn build_cli() -> CliCommand {
+    CliCommandBuilder::default()
         .set_name("RusticNotes")
         .set_version(ROOT_VERSION)
         .set_description("A simplistic tool for managing notes")
+        .add_global_option(
+            &CliCommandOption {
+                name: "project".to_string(),
+                short_name: Some("p".to_string()),
+                description: Some("Run the command in this project instead of the active one".to_string()),
+                is_flag: false,
+                ..Default::default()
+            }
+        )
         .add_subcommand(&note_commands::build_new_command())
         .add_subcommand(&note_commands::build_list_command())
         .add_subcommand(&note_commands::build_get_command())
         .add_subcommand(&note_commands::build_delete_command())
         .add_subcommand(&note_commands::build_search_command())
         .add_subcommand(&note_commands::build_edit_command())
-        .build();
-    cli.run(env::args());
+        .add_subcommand(&note_commands::build_history_command())
+        .add_subcommand(&note_commands::build_diff_command())
+        .add_subcommand(&note_commands::build_restore_command())
+        .add_subcommand(&note_commands::build_links_command())
+        .add_subcommand(&note_commands::build_backlinks_command())
+        .add_subcommand(&note_commands::build_graph_command())
+        .add_subcommand(&exchange_commands::build_import_command())
+        .add_subcommand(&exchange_commands::build_export_command())
+        .add_subcommand(&tag_commands::build_tag_command())
+        .add_subcommand(&tag_commands::build_tags_command())
+        .add_subcommand(&project_commands::build_project_command())
+        .add_subcommand(&tui::build_tui_command())
+        .add_subcommand(&completion_commands::build_completions_command())
+        .build()
 }
 
-// todo #947 better error handling
 // todo #948 add tests
-// todo #949 save notes in markdown/org-mode files with json as a manifest/metadata
-// todo #950 edit note tags and others
-// todo #951 projects support and persistant switching between them
-// todo #952 active tui
-// todo #954 expose api as a library for external usage
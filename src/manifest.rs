@@ -0,0 +1,325 @@
+use std::{collections::{BTreeMap, HashMap}, io::{self, Read, Seek, SeekFrom}, path::{Path, PathBuf}};
+use serde::{Serialize, Deserialize};
+use crate::error::{Result, RusticError};
+use crate::notes::Revision;
+use crate::storage;
+
+// the log is only rewritten when it holds at least this many records and more than half of them are stale
+const COMPACTION_MIN_RECORDS: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NoteMetadata {
+    pub id: u32,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+    #[serde(default)]
+    pub revisions: Vec<Revision>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum ManifestRecord {
+    Put(NoteMetadata),
+    Delete { id: u32 },
+}
+
+// snapshot of the offset index, written on compaction so opening the manifest only has to replay the records appended after it
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct ManifestIndex {
+    log_length: u64,
+    record_count: usize,
+    offsets: BTreeMap<u32, u64>,
+}
+
+/// Append-only log of note metadata.
+///
+/// Every change appends a single record to `manifest.log`, the latest record of each note is found
+/// through an offset index, and stale records are dropped by a periodic compaction.
+#[derive(Debug)]
+pub struct Manifest {
+    log_file: PathBuf,
+    index_file: PathBuf,
+    index: ManifestIndex,
+}
+
+impl Manifest {
+    pub fn open(directory: &Path) -> Result<Manifest> {
+        let mut manifest = Manifest {
+            log_file: directory.join("manifest.log"),
+            index_file: directory.join("manifest.idx"),
+            index: ManifestIndex::default(),
+        };
+        manifest.migrate_json_manifest(&directory.join("manifest.json"))?;
+
+        let log_length = match std::fs::metadata(&manifest.log_file) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(manifest),
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Ok(data) = std::fs::read_to_string(&manifest.index_file) {
+            manifest.index = serde_json::from_str(&data).unwrap_or_default();
+        }
+        // the snapshot is of no use if the log got shorter since it was taken, replay everything instead
+        if manifest.index.log_length > log_length {
+            manifest.index = ManifestIndex::default();
+        }
+        manifest.replay_log()?;
+        Ok(manifest)
+    }
+
+    pub fn exists(directory: &Path) -> bool {
+        directory.join("manifest.log").exists() || directory.join("manifest.json").exists()
+    }
+
+    pub fn get(&self, id: u32) -> Result<Option<NoteMetadata>> {
+        let Some(&offset) = self.index.offsets.get(&id) else {
+            return Ok(None);
+        };
+
+        let mut file = std::fs::File::open(&self.log_file)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        io::BufRead::read_line(&mut io::BufReader::new(file), &mut line)?;
+        match self.parse_record(&line, offset)? {
+            ManifestRecord::Put(metadata) => Ok(Some(metadata)),
+            ManifestRecord::Delete { .. } => Ok(None),
+        }
+    }
+
+    pub fn entries(&self) -> Result<HashMap<u32, NoteMetadata>> {
+        if self.index.offsets.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut data = String::new();
+        std::fs::File::open(&self.log_file)?.read_to_string(&mut data)?;
+
+        let mut entries = HashMap::new();
+        for &offset in self.index.offsets.values() {
+            let Some(line) = data.get(offset as usize..).and_then(|rest| rest.lines().next()) else {
+                return Err(self.corruption_error(offset, "the offset index points outside of the log"));
+            };
+            if let ManifestRecord::Put(metadata) = self.parse_record(line, offset)? {
+                entries.insert(metadata.id, metadata);
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn put(&mut self, metadata: &NoteMetadata) -> Result<()> {
+        let offset = self.append(&ManifestRecord::Put(metadata.clone()))?;
+        self.index.offsets.insert(metadata.id, offset);
+        self.compact_if_needed()
+    }
+
+    pub fn remove(&mut self, id: u32) -> Result<()> {
+        if !self.index.offsets.contains_key(&id) {
+            return Ok(());
+        }
+        self.append(&ManifestRecord::Delete { id })?;
+        self.index.offsets.remove(&id);
+        self.compact_if_needed()
+    }
+
+    pub fn compact(&mut self) -> Result<()> {
+        let mut entries: Vec<NoteMetadata> = self.entries()?.into_values().collect();
+        entries.sort_by_key(|metadata| metadata.id);
+
+        let mut data = String::new();
+        let mut index = ManifestIndex::default();
+        for metadata in entries {
+            index.offsets.insert(metadata.id, data.len() as u64);
+            data.push_str(&serde_json::to_string(&ManifestRecord::Put(metadata))?);
+            data.push('\n');
+        }
+        index.log_length = data.len() as u64;
+        index.record_count = index.offsets.len();
+
+        // drop the old snapshot first, a crash before the new one is written then only costs a full replay
+        if self.index_file.exists() {
+            storage::remove_durably(&self.index_file)?;
+        }
+        storage::write_atomically(&self.log_file, data.as_bytes())?;
+        storage::write_atomically(&self.index_file, serde_json::to_string(&index)?.as_bytes())?;
+        self.index = index;
+        Ok(())
+    }
+
+    fn compact_if_needed(&mut self) -> Result<()> {
+        let record_count = self.index.record_count;
+        if record_count >= COMPACTION_MIN_RECORDS && record_count > self.index.offsets.len() * 2 {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, record: &ManifestRecord) -> Result<u64> {
+        let line = format!("{}\n", serde_json::to_string(record)?);
+        let offset = storage::append_durably(&self.log_file, line.as_bytes())?;
+        self.index.log_length = offset + line.len() as u64;
+        self.index.record_count += 1;
+        Ok(offset)
+    }
+
+    fn replay_log(&mut self) -> Result<()> {
+        let mut file = std::fs::File::open(&self.log_file)?;
+        file.seek(SeekFrom::Start(self.index.log_length))?;
+        let mut data = String::new();
+        file.read_to_string(&mut data)?;
+
+        let mut offset = self.index.log_length;
+        for line in data.split_inclusive('\n') {
+            if !line.trim().is_empty() {
+                match self.parse_record(line, offset)? {
+                    ManifestRecord::Put(metadata) => { self.index.offsets.insert(metadata.id, offset); },
+                    ManifestRecord::Delete { id } => { self.index.offsets.remove(&id); },
+                }
+                self.index.record_count += 1;
+            }
+            offset += line.len() as u64;
+        }
+        self.index.log_length = offset;
+        Ok(())
+    }
+
+    fn parse_record(&self, line: &str, offset: u64) -> Result<ManifestRecord> {
+        if !line.ends_with('\n') && !line.ends_with('}') {
+            return Err(self.corruption_error(offset, "the record was only partially written"));
+        }
+        serde_json::from_str(line).map_err(|e| self.corruption_error(offset, &e.to_string()))
+    }
+
+    fn corruption_error(&self, offset: u64, reason: &str) -> RusticError {
+        RusticError::Parse(format!(
+            "{} is corrupted at byte {offset}: {reason}. Restore it from a snapshot in the backups directory.",
+            self.log_file.display()
+        ))
+    }
+
+    // manifests used to be a single json document rewritten on every save
+    fn migrate_json_manifest(&mut self, json_manifest_file: &Path) -> Result<()> {
+        #[derive(Deserialize)]
+        struct JsonManifest {
+            notes: Vec<NoteMetadata>,
+        }
+
+        if !json_manifest_file.exists() || self.log_file.exists() {
+            return Ok(());
+        }
+
+        let json_manifest: JsonManifest = serde_json::from_str(&std::fs::read_to_string(json_manifest_file)?)?;
+        for metadata in &json_manifest.notes {
+            let offset = self.append(&ManifestRecord::Put(metadata.clone()))?;
+            self.index.offsets.insert(metadata.id, offset);
+        }
+        self.compact()?;
+        Ok(std::fs::remove_file(json_manifest_file)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("rustic_notes_manifest_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn metadata(id: u32, title: &str) -> NoteMetadata {
+        NoteMetadata { id, tags: vec![], created_at: 0, updated_at: 0, revisions: vec![], title: Some(title.to_string()) }
+    }
+
+    fn title(manifest: &Manifest, id: u32) -> Option<String> {
+        manifest.get(id).unwrap().and_then(|metadata| metadata.title)
+    }
+
+    #[test]
+    fn replays_puts_and_deletes_on_open() {
+        let directory = temp_directory("replay");
+        let mut manifest = Manifest::open(&directory).unwrap();
+        manifest.put(&metadata(1, "first")).unwrap();
+        manifest.put(&metadata(2, "second")).unwrap();
+        manifest.put(&metadata(1, "first again")).unwrap();
+        manifest.remove(2).unwrap();
+
+        let manifest = Manifest::open(&directory).unwrap();
+        assert_eq!(title(&manifest, 1).as_deref(), Some("first again"));
+        assert_eq!(title(&manifest, 2), None);
+        assert_eq!(manifest.entries().unwrap().len(), 1);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn fails_on_a_partially_written_record() {
+        let directory = temp_directory("partial");
+        let mut manifest = Manifest::open(&directory).unwrap();
+        manifest.put(&metadata(1, "first")).unwrap();
+        storage::append_durably(&directory.join("manifest.log"), br#"{"op":"put","id":2,"ta"#).unwrap();
+
+        let error = Manifest::open(&directory).unwrap_err().to_string();
+        assert!(error.contains("partially written"), "{error}");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn reports_the_offset_of_a_corrupted_record() {
+        let directory = temp_directory("corrupted");
+        let mut manifest = Manifest::open(&directory).unwrap();
+        manifest.put(&metadata(1, "first")).unwrap();
+        let offset = std::fs::metadata(directory.join("manifest.log")).unwrap().len();
+        storage::append_durably(&directory.join("manifest.log"), b"not json\n").unwrap();
+
+        let error = Manifest::open(&directory).unwrap_err().to_string();
+        assert!(error.contains(&format!("corrupted at byte {offset}")), "{error}");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn compacts_stale_records_and_keeps_the_offset_index() {
+        let directory = temp_directory("compaction");
+        let mut manifest = Manifest::open(&directory).unwrap();
+        for revision in 0..COMPACTION_MIN_RECORDS {
+            manifest.put(&metadata(1, &format!("revision {revision}"))).unwrap();
+        }
+        manifest.put(&metadata(2, "second")).unwrap();
+
+        let log = std::fs::read_to_string(directory.join("manifest.log")).unwrap();
+        assert!(log.lines().count() < COMPACTION_MIN_RECORDS, "the log was not compacted");
+        assert!(directory.join("manifest.idx").exists());
+
+        // records appended after the snapshot are replayed on top of it
+        manifest.put(&metadata(3, "third")).unwrap();
+        let manifest = Manifest::open(&directory).unwrap();
+        assert_eq!(title(&manifest, 1), Some(format!("revision {}", COMPACTION_MIN_RECORDS - 1)));
+        assert_eq!(title(&manifest, 2).as_deref(), Some("second"));
+        assert_eq!(title(&manifest, 3).as_deref(), Some("third"));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn replays_everything_when_the_snapshot_is_ahead_of_the_log() {
+        let directory = temp_directory("stale_snapshot");
+        let mut manifest = Manifest::open(&directory).unwrap();
+        manifest.put(&metadata(1, "first")).unwrap();
+        manifest.put(&metadata(2, "second")).unwrap();
+        manifest.compact().unwrap();
+
+        // a log restored from an older backup is shorter than the snapshot describes
+        let record = format!("{}\n", serde_json::to_string(&ManifestRecord::Put(metadata(1, "restored"))).unwrap());
+        storage::write_atomically(&directory.join("manifest.log"), record.as_bytes()).unwrap();
+
+        let manifest = Manifest::open(&directory).unwrap();
+        assert_eq!(title(&manifest, 1).as_deref(), Some("restored"));
+        assert_eq!(title(&manifest, 2), None);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,61 +1,82 @@
-use crate::cli_command::{CliCommandBuilder, CliCommand, CliCommandOption};
-use crate::{notes, print_utils, config};
-use std::{collections::HashMap, io::Write};
+use crate::cli_command::{ArgumentType, CliCommandArgument, CliCommandBuilder, CliCommand, CliCommandOption, ParsedArgs};
+use crate::print_utils;
+use rustic_notes::{config, diff, fuzzy::{self, FuzzyMatch}, graph::NoteGraph, notes::{NoteStore, RusticNote, TagFilter, TagMatch, TimestampField}, query::Query, search::{self, SearchIndex}, time_utils, Result, RusticError, Workspace};
+use regex::Regex;
+use std::io::Write;
 
 pub fn build_new_command() -> CliCommand {
     CliCommandBuilder::default()
         .set_name("new")
         .add_alias("add")
         .set_description("Create a new note")
-        .add_argument("note")
+        .add_argument(
+            &CliCommandArgument {
+                name: "note".to_string(),
+                description: Some("Content of the note, an editor is opened without it".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: false,
+                is_variadic: true
+            }
+        )
         .add_option(
             &CliCommandOption {
                 name: "interactive".to_string(),
                 short_name: Some("i".to_string()),
                 description: Some("Create note interactivly through an external editor. One has to be provided through config or it will fail.".to_string()),
-                is_flag: false
+                is_flag: true,
+                ..Default::default()
             }
         )
         .add_option(
             &CliCommandOption {
                 name: "tag".to_string(),
                 short_name: Some("t".to_string()),
-                description: Some("Add a tag to the note".to_string()),
-                is_flag: false
+                description: Some("Add a tag to the note, several can be separated by commas".to_string()),
+                is_flag: false,
+                ..Default::default()
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "title".to_string(),
+                short_name: None,
+                description: Some("Title of the note, by default it is taken from a leading \"# heading\" line".to_string()),
+                is_flag: false,
+                ..Default::default()
             }
-        ).set_action(|args: HashMap<String, Vec<String>>| {
-            let note_content = if args.contains_key("interactive") || !args.contains_key("note") {
+        ).set_action(|args: ParsedArgs| {
+            let note_argument = get_query_argument(&args, "note")?;
+            let note_content = if args.flag("interactive") || note_argument.is_none() {
                 match get_from_editor(None) {
                     Ok(content) => content,
-                    Err(EditorOutputError) => {
-                        if args.contains_key("note") {
-                            args.get("note").and_then(|v| v.last()).unwrap_or(&String::new()).to_string()
+                    Err(e) => {
+                        if let Some(note) = note_argument {
+                            eprintln!("{}", print_utils::colorize(print_utils::Color::warning(), format!("{e} Using the note argument instead.").as_str()));
+                            note
                         } else {
-                            return;
+                            return Err(e);
                         }
                     }
                 }
-            } else if let Some(note) = args.get("note") {
-                note.last().unwrap_or(&String::from("")).to_string()
             } else {
                 // todo #941 make it easier to write
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Note name is required."));
-                return;
+                note_argument.unwrap_or_default()
             };
 
             // todo #942 ask if user wants to create empty note anyway
             if note_content.trim().is_empty() {
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Note content is empty, not creating note."));
-                return;
+                return Err(RusticError::Usage("Note content is empty, not creating note.".to_string()));
             }
 
             println!("Creating new note: {note_content}");
-            let tags: Vec<String> = args.get("tag").unwrap_or(&vec![]).clone();
+            let tags = split_tags(&args.get_many::<String>("tag")?);
             if !tags.is_empty() {
                 println!("With tags: {tags:?}");
             }
-            let new_note = notes::RusticNote::new(note_content.trim().to_string(), tags);
-            notes::save_note(&new_note);
+            let title: Option<String> = args.get_one("title")?;
+            open_store(&args)?.create(note_content.trim(), title.as_deref(), tags)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), "Note saved successfully."));
+            Ok(())
         }).build()
 }
 
@@ -63,57 +84,109 @@ pub fn build_list_command() -> CliCommand {
     CliCommandBuilder::default()
         .set_name("list")
         .add_alias("ls")
-        .set_description("List all notes")
+        .set_description("List all notes, or only those matching a filter expression in the search query language")
+        .add_argument(
+            &CliCommandArgument {
+                name: "filter".to_string(),
+                description: Some("Only list notes matching this query, e.g. 'tag:work created:>2026-01-01'".to_string()),
+                value_type: ArgumentType::Query,
+                is_required: false,
+                is_variadic: true
+            }
+        )
+        .add_options(&tag_filter_options())
         .add_option(
             &CliCommandOption {
-                name: "tag".to_string(),
-                short_name: Some("t".to_string()),
-                description: Some("Search by a tag".to_string()),
-                is_flag: false
+                name: "sort".to_string(),
+                short_name: Some("s".to_string()),
+                description: Some("Sort by the most recently created or updated notes first".to_string()),
+                is_flag: false,
+                value_type: ArgumentType::Choice(vec!["created".to_string(), "updated".to_string()]),
+                ..Default::default()
+            }
+        ).add_option(
+            &CliCommandOption {
+                name: "since".to_string(),
+                short_name: None,
+                description: Some("Only list notes created (or updated, with --sort updated) on or after this date".to_string()),
+                is_flag: false,
+                validator: Some(validate_date),
+                ..Default::default()
+            }
+        ).add_option(
+            &CliCommandOption {
+                name: "until".to_string(),
+                short_name: None,
+                description: Some("Only list notes created (or updated, with --sort updated) on or before this date".to_string()),
+                is_flag: false,
+                validator: Some(validate_date),
+                ..Default::default()
+            }
+        ).set_action(|args: ParsedArgs| {
+            let sort_field = args.get_one::<String>("sort")?.and_then(|sort| TimestampField::parse(&sort));
+            let date_field = sort_field.unwrap_or(TimestampField::Created);
+            let since = args.get_one::<String>("since")?.map(|date| time_utils::parse_date_span(&date)).transpose()?;
+            let until = args.get_one::<String>("until")?.map(|date| time_utils::parse_date_span(&date)).transpose()?;
+
+            let filter = get_query_argument(&args, "filter")?.map(|filter| Query::parse(&filter)).transpose()?;
+            let tag_filter = parse_tag_filter(&args)?;
+
+            let store = open_store(&args)?;
+            let mut notes = store.list()?;
+            if let Some(filter) = filter {
+                notes = filter.filter(notes, &store.search_index()?);
+            }
+            notes.retain(|note| tag_filter.matches(note));
+            if let Some((since_start, _)) = since {
+                notes.retain(|note| date_field.of(note) >= since_start);
+            }
+            if let Some((_, until_end)) = until {
+                notes.retain(|note| date_field.of(note) < until_end);
             }
-        ).set_action(|args: HashMap<String, Vec<String>>| {
-            let mut notes = notes::load_all_notes();
+
             if notes.is_empty() {
                 println!("{}", print_utils::colorize(print_utils::Color::warning(), "No notes found."));
             } else {
-                let tags = args.get("tag").unwrap_or(&vec![]).clone();
-
-                if !tags.is_empty() {
-                    notes.retain(|note| note.tags.iter().any(|tag| tags.contains(tag)));
+                if let Some(field) = sort_field {
+                    notes.sort_by_key(|note| std::cmp::Reverse(field.of(note)));
                 }
 
                 println!("Notes:");
                 for note in notes {
-                    let note_content = if note.content.len() > 50 {
-                        format!("{}...", &note.content[..47])
-                    } else {
-                        note.content.clone()
-                    };
-                    println!("{}. {}", note.id, note_content);
+                    let note_content = note_label(&note);
+                    let timestamp = time_utils::format_timestamp(sort_field.unwrap_or(TimestampField::Updated).of(&note));
+                    println!("{}. {note_content:<50} {timestamp}", note.id);
                 }
             }
+            Ok(())
         }).build()
 }
 
 pub fn build_get_command() -> CliCommand {
     CliCommandBuilder::default()
         .set_name("get")
-        .set_description("Get a single note by its id")
-        .add_argument("id")
-        .set_action(|args: HashMap<String, Vec<String>>| {
-            if let Some(id_str) = args.get("id").and_then(|v| v.last()) {
-                if let Ok(id) = id_str.parse::<u32>() {
-                    if let Some(note) = notes::get_note_by_id(id) {
-                        println!("{}", note.content);
-                    } else {
-                        eprintln!("{}", print_utils::colorize(print_utils::Color::warning(), format!("Note with id {id} not found.").as_str()));
-                    }
-                } else {
-                    eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Invalid id: {id_str}").as_str()));
-                }
-            } else {
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Note id is required."));
+        .set_description("Get a single note by its id or title")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id or title of the note".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
             }
+        )
+        .set_action(|args: ParsedArgs| {
+            let note = find_note(&open_store(&args)?, &args)?;
+            let title = note.title().map_or(String::new(), |title| format!(" {title},"));
+            println!(
+                "{}",
+                print_utils::colorize(
+                    print_utils::Color::warning(),
+                    format!("#{}{title} created {}, updated {}", note.id, time_utils::format_timestamp(note.created_at), time_utils::format_timestamp(note.updated_at)).as_str()
+                )
+            );
+            println!("{}", note.content);
+            Ok(())
         }).build()
 }
 
@@ -122,174 +195,650 @@ pub fn build_delete_command() -> CliCommand {
         .set_name("delete")
         .add_alias("remove")
         .add_alias("rm")
-        .set_description("Delete a single note by its id")
-        .add_argument("id")
-        .set_action(|args: HashMap<String, Vec<String>>| {
-            if let Some(id_str) = args.get("id").and_then(|v| v.last()) {
-                if let Ok(id) = id_str.parse::<u32>() {
-                    if notes::get_note_by_id(id).is_some() {
-                        notes::remove_note_by_id(id);
-                    } else {
-                        eprintln!("{}", print_utils::colorize(print_utils::Color::warning(), format!("Note with id {id} not found.").as_str()));
-                    }
-                } else {
-                    eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Invalid id: {id_str}").as_str()));
+        .set_description("Delete a single note by its id or title")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id or title of the note".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let store = open_store(&args)?;
+            let note = find_note(&store, &args)?;
+            let previous_graph = store.link_graph()?;
+            store.delete(note.id)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), "Note deleted successfully."));
+
+            // links that led to the deleted note and now lead nowhere
+            let graph = store.link_graph()?;
+            for source_id in previous_graph.backlinks(note.id) {
+                let broken_links = previous_graph.links(source_id).iter()
+                    .zip(graph.links(source_id))
+                    .filter(|(previous_link, link)| previous_link.note_id == Some(note.id) && link.note_id.is_none());
+                for (_, link) in broken_links {
+                    eprintln!("{}", print_utils::colorize(print_utils::Color::warning(), format!("Warning: note {source_id} has a broken link {}.", link.target).as_str()));
+                }
+            }
+            Ok(())
+        }).build()
+}
+
+pub fn build_links_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("links")
+        .set_description("List the notes a note links to with [[title]] or [[#id]]")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id or title of the note".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let store = open_store(&args)?;
+            let note = find_note(&store, &args)?;
+            let graph = store.link_graph()?;
+            let notes = store.list()?;
+
+            let links = graph.links(note.id);
+            if links.is_empty() {
+                println!("{}", print_utils::colorize(print_utils::Color::warning(), "No links found."));
+                return Ok(());
+            }
+            println!("Links:");
+            for link in links {
+                match link.note_id.and_then(|id| notes.iter().find(|n| n.id == id)) {
+                    Some(linked_note) => println!("{}. {}", linked_note.id, note_label(linked_note)),
+                    None => println!("{}", print_utils::colorize(print_utils::Color::error(), format!("{} (broken)", link.target).as_str())),
                 }
-            } else {
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Note id is required."));
             }
+            Ok(())
+        }).build()
+}
+
+pub fn build_backlinks_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("backlinks")
+        .set_description("List the notes linking to a note")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id or title of the note".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let store = open_store(&args)?;
+            let note = find_note(&store, &args)?;
+            let backlinks = store.link_graph()?.backlinks(note.id);
+            if backlinks.is_empty() {
+                println!("{}", print_utils::colorize(print_utils::Color::warning(), "No backlinks found."));
+                return Ok(());
+            }
+
+            println!("Backlinks:");
+            for linking_note in store.list()?.iter().filter(|n| backlinks.contains(&n.id)) {
+                println!("{}. {}", linking_note.id, note_label(linking_note));
+            }
+            Ok(())
+        }).build()
+}
+
+pub fn build_graph_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("graph")
+        .set_description("Export how notes relate through tags and references, for rendering with Graphviz")
+        .add_option(
+            &CliCommandOption {
+                name: "format".to_string(),
+                short_name: Some("f".to_string()),
+                description: Some("Output as a Graphviz graph or as JSON".to_string()),
+                is_flag: false,
+                default_value: Some("dot".to_string()),
+                value_type: ArgumentType::Choice(vec!["dot".to_string(), "json".to_string()]),
+                ..Default::default()
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "output".to_string(),
+                short_name: Some("o".to_string()),
+                description: Some("Write to this file instead of printing".to_string()),
+                is_flag: false,
+                ..Default::default()
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let graph = NoteGraph::build(&open_store(&args)?.list()?);
+            let output = match args.get_required::<String>("format")?.as_str() {
+                "json" => graph.to_json()? + "\n",
+                _ => graph.to_dot(),
+            };
+
+            match args.get_one::<String>("output")? {
+                Some(path) => {
+                    std::fs::write(&path, output)?;
+                    println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Graph written to {path}.").as_str()));
+                },
+                None => print!("{output}"),
+            }
+            Ok(())
         }).build()
 }
 
 pub fn build_search_command() -> CliCommand {
     CliCommandBuilder::default()
         .set_name("search")
-        .set_description("Search for a note by a query string")
-        .add_argument("query")
+        .set_description("Search for notes by a query, e.g. 'tag:work AND (deploy OR rollback) -tag:archived created:>2026-01-01'")
+        .add_argument(
+            &CliCommandArgument {
+                name: "query".to_string(),
+                description: Some("Query to search for, or a regular expression with --regex".to_string()),
+                value_type: ArgumentType::Query,
+                is_required: false,
+                is_variadic: true
+            }
+        )
+        .add_options(&tag_filter_options())
         .add_option(
             &CliCommandOption {
-                name: "tag".to_string(),
-                short_name: Some("t".to_string()),
-                description: Some("Narrow search to a tag".to_string()),
-                is_flag: false
+                name: "fuzzy".to_string(),
+                short_name: Some("f".to_string()),
+                description: Some("Match the query loosely, tolerating typos and missing characters, best matches first".to_string()),
+                is_flag: true,
+                ..Default::default()
             }
         )
-        .set_action(|args: HashMap<String, Vec<String>>| {
-            let query = args.get("query").and_then(|v| v.last());
-            let tags = args.get("tag");
-
-            if query.is_none() && tags.is_none() {
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Query is required."));
+        .add_option(
+            &CliCommandOption {
+                name: "regex".to_string(),
+                short_name: Some("r".to_string()),
+                description: Some("Treat the query as a regular expression and print the matching lines".to_string()),
+                is_flag: true,
+                ..Default::default()
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "after-context".to_string(),
+                short_name: Some("A".to_string()),
+                description: Some("Print this many lines after every regex match".to_string()),
+                is_flag: false,
+                value_type: ArgumentType::Number,
+                ..Default::default()
             }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "before-context".to_string(),
+                short_name: Some("B".to_string()),
+                description: Some("Print this many lines before every regex match".to_string()),
+                is_flag: false,
+                value_type: ArgumentType::Number,
+                ..Default::default()
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "context".to_string(),
+                short_name: Some("C".to_string()),
+                description: Some("Print this many lines before and after every regex match".to_string()),
+                is_flag: false,
+                value_type: ArgumentType::Number,
+                ..Default::default()
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let query = get_query_argument(&args, "query")?;
+            let query = query.as_deref();
+            let tag_filter = parse_tag_filter(&args)?;
 
-            let mut all_notes = notes::load_all_notes();
+            if query.is_none() && tag_filter.is_empty() {
+                return Err(RusticError::Usage("Query is required.".to_string()));
+            }
+            if args.flag("fuzzy") && args.flag("regex") {
+                return Err(RusticError::Usage("--fuzzy and --regex cannot be combined.".to_string()));
+            }
+            let has_context = ["context", "before-context", "after-context"].iter().any(|name| args.flag(name));
+            if has_context && !args.flag("regex") {
+                return Err(RusticError::Usage("-A, -B and -C only apply to a --regex search.".to_string()));
+            }
 
-            // filter by tags
-            if let Some(tags_list) = tags {
-                all_notes.retain(|n| n.tags.iter().any(|t| tags_list.contains(t)));
+            let store = open_store(&args)?;
+            if args.flag("fuzzy") {
+                let Some(query) = query else {
+                    return Err(RusticError::Usage("Query is required for a fuzzy search.".to_string()));
+                };
+                let notes = search_notes(store.list()?, &store.search_index()?, None, &tag_filter)?;
+                print_fuzzy_matches(notes, query);
+                return Ok(());
             }
+            if args.flag("regex") {
+                let Some(query) = query else {
+                    return Err(RusticError::Usage("Query is required for a regex search.".to_string()));
+                };
+                let regex = Regex::new(query).map_err(|e| RusticError::Usage(format!("Invalid regex: {e}")))?;
+                let context = args.get_one::<usize>("context")?.unwrap_or(0);
+                let before = args.get_one::<usize>("before-context")?.unwrap_or(context);
+                let after = args.get_one::<usize>("after-context")?.unwrap_or(context);
 
-            // filter by query
-            if let Some(query_string) = query {
-                all_notes = notes::slow_search(&all_notes, query_string)
+                let notes = search_notes(store.list()?, &store.search_index()?, None, &tag_filter)?;
+                print_regex_matches(&notes, &regex, before, after);
+                return Ok(());
             }
 
+            let all_notes = search_notes(store.list()?, &store.search_index()?, query, &tag_filter)?;
+
             if all_notes.is_empty() {
                 println!("{}", print_utils::colorize(print_utils::Color::warning(), "No notes found."));
             } else {
+                let parsed_query = query.map(Query::parse).transpose()?;
                 println!("Notes:");
                 for note in all_notes {
-                    println!("{}. {}", note.id, note.content);
+                    let content = match &parsed_query {
+                        Some(parsed_query) => print_utils::highlight(&note.content, &parsed_query.highlight_ranges(&note.content)),
+                        None => note.content,
+                    };
+                    println!("{}. {}", note.id, content);
                 }
             }
+            Ok(())
         }).build()
 }
 
 pub fn build_edit_command() -> CliCommand {
     CliCommandBuilder::default()
         .set_name("edit")
-        .set_description("Edit a single note by its id")
-        .add_argument("id")
+        .set_description("Edit a single note by its id or title")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id or title of the note".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
         .add_option(
             &CliCommandOption {
                 name: "message".to_string(),
                 short_name: Some("m".to_string()),
                 description: Some("Replace note by this string. If --interactive option is passed, it is discarded.".to_string()),
-                is_flag: false
+                is_flag: false,
+                ..Default::default()
             }
         ).add_option(
             &CliCommandOption {
                 name: "interactive".to_string(),
                 short_name: Some("i".to_string()),
                 description: Some("Edit note interactivly through an external editor. One has to be provided through config or it will fail.".to_string()),
-                is_flag: false
-            }
-        ).set_action(|args: HashMap<String, Vec<String>>| {
-            let id_str = args.get("id").and_then(|v| v.last());
-            let id = match id_str {
-                Some(id) => match id.parse::<u32>() {
-                    Ok(id) => id,
-                    Err(_) => {
-                        eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Invalid id: {id}").as_str()));
-                        return;
-                    }
-                },
-                None => {
-                    eprintln!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Note id is required."));
-                    return;
-                }
-            };
+                is_flag: true,
+                ..Default::default()
+            }
+        ).add_option(
+            &CliCommandOption {
+                name: "title".to_string(),
+                short_name: None,
+                description: Some("Set the title of the note, only the title changes unless --message or --interactive is passed too".to_string()),
+                is_flag: false,
+                ..Default::default()
+            }
+        ).set_action(|args: ParsedArgs| {
+            let store = open_store(&args)?;
+            let mut note = find_note(&store, &args)?;
 
-            let mut note = match notes::get_note_by_id(id) {
-                Some(note) => note,
-                None => {
-                    eprintln!("{}", print_utils::colorize(print_utils::Color::warning(), format!("Note with id {id} not found.").as_str()));
-                    return;
+            let message: Option<String> = args.get_one("message")?;
+            if let Some(title) = args.get_one::<String>("title")? {
+                note.title = Some(title);
+                if message.is_none() && !args.flag("interactive") {
+                    store.update(&note)?;
+                    println!("{}", print_utils::colorize(print_utils::Color::success(), "Note saved successfully."));
+                    return Ok(());
                 }
-            };
+            }
 
-            let edited_note_content = if args.contains_key("interactive") || !args.contains_key("message") {
+            let edited_note_content = if args.flag("interactive") || message.is_none() {
                 match get_from_editor(Some(note.content)) {
                     Ok(content) => content,
-                    Err(EditorOutputError) => {
-                        if args.contains_key("message") {
-                            args.get("message").and_then(|v| v.last()).unwrap_or(&String::new()).to_string()
+                    Err(e) => {
+                        if let Some(message) = message {
+                            eprintln!("{}", print_utils::colorize(print_utils::Color::warning(), format!("{e} Using the message option instead.").as_str()));
+                            message
                         } else {
-                            return;
+                            return Err(e);
                         }
                     }
                 }
             } else {
-                args.get("message").and_then(|v| v.last()).unwrap_or(&String::new()).to_string()
+                message.unwrap_or_default()
             };
 
             note.content = edited_note_content.trim().to_string();
-            notes::save_note(&note);
+            store.update(&note)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), "Note saved successfully."));
+            Ok(())
         }).build()
 }
 
-struct EditorOutputError;
+pub fn build_history_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("history")
+        .set_description("List all revisions of a note")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id of the note".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let id: u32 = args.get_required("id")?;
+            let revisions = open_store(&args)?.history(id)?;
+            let current_number = revisions.last().map_or(1, |revision| revision.number);
+
+            println!("Revisions:");
+            for revision in revisions {
+                let first_line = revision.content.lines().next().unwrap_or_default();
+                let preview = if first_line.chars().count() > 50 {
+                    format!("{}...", first_line.chars().take(47).collect::<String>())
+                } else {
+                    first_line.to_string()
+                };
+                let marker = if revision.number == current_number { " (current)" } else { "" };
+                println!("{}. {} {preview}{marker}", revision.number, time_utils::format_timestamp(revision.updated_at));
+            }
+            Ok(())
+        }).build()
+}
+
+pub fn build_diff_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("diff")
+        .set_description("Show changes between two revisions of a note, by default the last change")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id of the note".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .add_argument(
+            &CliCommandArgument {
+                name: "rev_a".to_string(),
+                description: Some("Revision to compare from, by default the one before rev_b".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: false,
+                is_variadic: false
+            }
+        )
+        .add_argument(
+            &CliCommandArgument {
+                name: "rev_b".to_string(),
+                description: Some("Revision to compare to, by default the current one".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: false,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let id: u32 = args.get_required("id")?;
+            let revisions = open_store(&args)?.history(id)?;
+            let current_number = revisions.last().map_or(1, |revision| revision.number);
+
+            let rev_b = args.get_one("rev_b")?.unwrap_or(current_number);
+            let rev_a = args.get_one("rev_a")?.unwrap_or(current_number.saturating_sub(1).max(1));
+            let find_revision = |number: u32| {
+                revisions.iter()
+                    .find(|revision| revision.number == number)
+                    .ok_or_else(|| RusticError::NotFound(format!("Revision {number} of note {id}")))
+            };
+            let (revision_a, revision_b) = (find_revision(rev_a)?, find_revision(rev_b)?);
+
+            let diff_output = diff::unified_diff(
+                &revision_a.content,
+                &revision_b.content,
+                &format!("#{id} revision {rev_a}"),
+                &format!("#{id} revision {rev_b}"),
+                3
+            );
+            if diff_output.is_empty() {
+                println!("{}", print_utils::colorize(print_utils::Color::warning(), "No differences."));
+            }
+            for line in diff_output.lines() {
+                if line.starts_with("+++") || line.starts_with("---") {
+                    println!("{line}");
+                } else if line.starts_with('+') {
+                    println!("{}", print_utils::colorize(print_utils::Color::success(), line));
+                } else if line.starts_with('-') {
+                    println!("{}", print_utils::colorize(print_utils::Color::error(), line));
+                } else if line.starts_with("@@") {
+                    println!("{}", print_utils::colorize(print_utils::Color::warning(), line));
+                } else {
+                    println!("{line}");
+                }
+            }
+            Ok(())
+        }).build()
+}
+
+pub fn build_restore_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("restore")
+        .set_description("Restore a note to an earlier revision, keeping its history")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id of the note".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .add_argument(
+            &CliCommandArgument {
+                name: "rev".to_string(),
+                description: Some("Revision to restore".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let id: u32 = args.get_required("id")?;
+            let rev: u32 = args.get_required("rev")?;
+            let note = open_store(&args)?.restore(id, rev)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Note {id} restored to revision {rev} as revision {}.", note.revision_number()).as_str()));
+            Ok(())
+        }).build()
+}
+
+fn print_fuzzy_matches(notes: Vec<RusticNote>, pattern: &str) {
+    let mut matches: Vec<(RusticNote, FuzzyMatch)> = notes.into_iter()
+        .filter_map(|note| fuzzy::fuzzy_match(pattern, &note.content).map(|fuzzy_match| (note, fuzzy_match)))
+        .collect();
+    matches.sort_by(|(a, a_match), (b, b_match)| b_match.score.cmp(&a_match.score).then(a.id.cmp(&b.id)));
+
+    if matches.is_empty() {
+        println!("{}", print_utils::colorize(print_utils::Color::warning(), "No notes found."));
+        return;
+    }
+    println!("Notes:");
+    for (note, fuzzy_match) in matches {
+        println!("{}. {}", note.id, print_utils::highlight(&note.content, &fuzzy_match.ranges));
+    }
+}
+
+// grep style, `<id>:<line>:` in front of matching lines and `<id>-<line>-` in front of context lines
+fn print_regex_matches(notes: &[RusticNote], regex: &Regex, before: usize, after: usize) {
+    let mut printed_any = false;
+    for note in notes {
+        for group in search::regex_matches(&note.content, regex, before, after) {
+            if printed_any && (before > 0 || after > 0) {
+                println!("--");
+            }
+            printed_any = true;
+
+            for line in group {
+                let separator = if line.is_match { ':' } else { '-' };
+                let prefix = print_utils::colorize(print_utils::Color::success(), &format!("{}{separator}{}{separator}", note.id, line.number));
+                println!("{prefix}{}", print_utils::highlight(line.line, &line.ranges));
+            }
+        }
+    }
+
+    if !printed_any {
+        println!("{}", print_utils::colorize(print_utils::Color::warning(), "No notes found."));
+    }
+}
+
+// shared by list, search and export
+pub fn tag_filter_options() -> Vec<CliCommandOption> {
+    vec![
+        CliCommandOption {
+            name: "tag".to_string(),
+            short_name: Some("t".to_string()),
+            description: Some("Only notes with this tag, including the tags below it".to_string()),
+            is_flag: false,
+            ..Default::default()
+        },
+        CliCommandOption {
+            name: "not-tag".to_string(),
+            short_name: Some("T".to_string()),
+            description: Some("Leave out notes with this tag, including the tags below it".to_string()),
+            is_flag: false,
+            ..Default::default()
+        },
+        CliCommandOption {
+            name: "tag-mode".to_string(),
+            short_name: None,
+            description: Some("Keep notes having any (the default) or all of the --tag tags".to_string()),
+            is_flag: false,
+            value_type: ArgumentType::Choice(vec!["any".to_string(), "all".to_string()]),
+            ..Default::default()
+        },
+        CliCommandOption {
+            name: "all-tags".to_string(),
+            short_name: None,
+            description: Some("Keep only notes having all of the --tag tags, same as --tag-mode all".to_string()),
+            is_flag: true,
+            ..Default::default()
+        },
+    ]
+}
+
+pub fn parse_tag_filter(args: &ParsedArgs) -> Result<TagFilter> {
+    let mode = match args.get_one::<String>("tag-mode")?.and_then(|mode| TagMatch::parse(&mode)) {
+        Some(TagMatch::Any) if args.flag("all-tags") => {
+            return Err(RusticError::Usage("--all-tags cannot be combined with --tag-mode any.".to_string()));
+        },
+        Some(mode) => mode,
+        None if args.flag("all-tags") => TagMatch::All,
+        None => TagMatch::Any,
+    };
+
+    Ok(TagFilter {
+        tags: split_tags(&args.get_many::<String>("tag")?),
+        excluded_tags: split_tags(&args.get_many::<String>("not-tag")?),
+        mode,
+    })
+}
+
+fn validate_date(date: &str) -> Result<()> {
+    time_utils::parse_date_span(date).map(|_| ())
+}
+
+// notes without a title are shown by the start of their content
+pub fn note_label(note: &RusticNote) -> String {
+    let label = note.title().unwrap_or_else(|| note.content.lines().next().unwrap_or_default().to_string());
+    if label.chars().count() > 50 {
+        format!("{}...", label.chars().take(47).collect::<String>())
+    } else {
+        label
+    }
+}
 
-fn get_from_editor(put_content: Option<String>) -> Result<String, EditorOutputError> {
-    let config = config::get_config();
+// the id argument of a command, which can be a title as well
+fn find_note(store: &NoteStore, args: &ParsedArgs) -> Result<RusticNote> {
+    store.find(&args.get_required::<String>("id")?)
+}
+
+// words of a variadic argument taken as a single string, so queries and notes need no quotes
+pub fn get_query_argument(args: &ParsedArgs, name: &str) -> Result<Option<String>> {
+    let words: Vec<String> = args.get_many(name)?;
+    Ok((!words.is_empty()).then(|| words.join(" ")))
+}
+
+// tags can be given one per option or several separated by commas
+pub fn split_tags(values: &[String]) -> Vec<String> {
+    values.iter()
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+// shared by the search command and the tui, so both always find the same notes
+pub fn search_notes(mut notes: Vec<RusticNote>, index: &SearchIndex, query: Option<&str>, tag_filter: &TagFilter) -> Result<Vec<RusticNote>> {
+    notes.retain(|note| tag_filter.matches(note));
+
+    // filter by query, best matches first
+    if let Some(query_string) = query {
+        let query = Query::parse(query_string)?;
+        notes = query.rank(query.filter(notes, index), index);
+    }
+    Ok(notes)
+}
+
+// every command works on the active project unless --project picks another one
+pub fn open_store(args: &ParsedArgs) -> Result<NoteStore> {
+    open_store_with_config(&config::get_config()?, args)
+}
+
+pub fn open_store_with_config(config: &config::RusticConfig, args: &ParsedArgs) -> Result<NoteStore> {
+    let workspace = Workspace::from_config(config);
+    match args.get_one::<String>("project")? {
+        Some(project) => workspace.open(&project),
+        None => workspace.open_active(),
+    }
+}
+
+pub fn get_from_editor(put_content: Option<String>) -> Result<String> {
+    let config = config::get_config()?;
     let editor = match config.editor {
         Some(e) => e,
-        None => {
-            eprintln!("{}", print_utils::colorize(print_utils::Color::error(), "No editor available!"));
-            return Err(EditorOutputError);
-        }
+        None => return Err(RusticError::Editor("No editor available!".to_string())),
     };
-    
+
     // save note to temporary file
     let temp_file_path = "/tmp/rustic_note_tmp.txt".to_string();
-    let mut file = match std::fs::File::create(&temp_file_path) {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error creating temporary file: {e}").as_str()));
-            return Err(EditorOutputError);
-        }
-    };
+    let mut file = std::fs::File::create(&temp_file_path)
+        .map_err(|e| RusticError::Editor(format!("Error creating temporary file: {e}")))?;
 
-    if put_content.is_some() {
-        if let Err(e) = file.write_all(put_content.unwrap_or_default().to_string().trim().as_bytes()) {
-            eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error writing note to temporary file: {e}").as_str()));
-            return Err(EditorOutputError);
-        }
+    if let Some(content) = put_content {
+        file.write_all(content.trim().as_bytes())
+            .map_err(|e| RusticError::Editor(format!("Error writing note to temporary file: {e}")))?;
     }
 
-    std::process::Command::new(editor)
+    let status = std::process::Command::new(&editor)
         .arg(&temp_file_path)
-        .spawn()
-        .unwrap_or_else(|_| {panic!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Failed to run editor"))})
-        .wait()
-        .unwrap_or_else(|_| {panic!("{}", print_utils::colorize(print_utils::Color::error(), "Error: Editor returned a non-zero status"))});
-    
-    // read the edited note back
-    match std::fs::read_to_string(&temp_file_path) {
-        Ok(content) => Ok(content),
-        Err(e) => {
-            eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error reading edited note: {e}").as_str()));
-            Err(EditorOutputError)
-        }
+        .status()
+        .map_err(|e| RusticError::Editor(format!("Failed to run editor '{editor}': {e}")))?;
+    if !status.success() {
+        return Err(RusticError::Editor(format!("Editor '{editor}' exited with {status}.")));
     }
+
+    // read the edited note back
+    std::fs::read_to_string(&temp_file_path)
+        .map_err(|e| RusticError::Editor(format!("Error reading edited note: {e}")))
 }
@@ -1,115 +1,647 @@
-use std::{io::Write, path::Path};
+use std::{collections::{BTreeMap, BTreeSet}, io, path::{Path, PathBuf}, time::UNIX_EPOCH};
 use serde::{Serialize, Deserialize};
-use crate::{print_utils, config};
+use crate::config::RusticConfig;
+use crate::error::{Result, RusticError};
+use crate::links::LinkGraph;
+use crate::manifest::{Manifest, NoteMetadata};
+use crate::query::Query;
+use crate::search::{Fingerprint, SearchIndex};
+use crate::search_log::SearchLog;
+use crate::storage;
+use crate::time_utils;
+
+// automatic snapshots are taken before the first change made after this many seconds since the last one
+const BACKUP_INTERVAL_SECS: u64 = 24 * 60 * 60;
+// everything a snapshot needs to bring the store back
+const BACKUP_ENTRIES: [&str; 4] = ["manifest.log", "manifest.idx", "notes", "history"];
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct RusticNote {
     pub id: u32,
     pub content: String,
-    pub tags: Vec<String>
+    pub tags: Vec<String>,
+    // unix seconds, notes saved before these were tracked have them set to 0
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+    // earlier versions of the content, oldest first
+    #[serde(default)]
+    pub revisions: Vec<Revision>,
+    // set explicitly, see `title()` for the one shown
+    #[serde(default)]
+    pub title: Option<String>,
 }
 
 impl RusticNote {
-    pub fn new(content: String, tags: Vec<String>) -> Self {
-        RusticNote { id: get_next_id(), content, tags }
+    pub fn new(id: u32, content: String, tags: Vec<String>) -> Self {
+        let now = time_utils::now();
+        RusticNote { id, content, tags, created_at: now, updated_at: now, revisions: vec![], title: None }
+    }
+
+    // an explicit title wins over a leading `# heading` line of the content
+    pub fn title(&self) -> Option<String> {
+        if let Some(title) = &self.title {
+            return Some(title.clone());
+        }
+        let first_line = self.content.lines().next()?;
+        let heading = first_line.strip_prefix("# ")?.trim();
+        if heading.is_empty() { None } else { Some(heading.to_string()) }
+    }
+
+    // tags are hierarchical, `work` also matches `work/infra` and everything below it
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| tag_matches(t, tag))
+    }
+
+    pub fn revision_number(&self) -> u32 {
+        self.revisions.last().map_or(1, |revision| revision.number + 1)
+    }
+
+    fn metadata(&self) -> NoteMetadata {
+        NoteMetadata {
+            id: self.id,
+            tags: self.tags.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            revisions: self.revisions.clone(),
+            title: self.title.clone(),
+        }
     }
 }
 
-pub fn save_notes(notes: Vec<RusticNote>) {
-    let config = config::get_config();
-    let notes_directory = Path::new(&config.notes_directory);
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
 
-    match notes_directory.try_exists() {
-        Ok(true) => {},
-        Ok(false) => {
-            if let Err(e) = std::fs::create_dir_all(notes_directory) {
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error creating notes directory: {e}").as_str()));
-                return;
-            }
-        },
-        Err(e) => {
-            eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error checking notes directory: {e}").as_str()));
-            return;
-        },
+impl TagMatch {
+    pub fn parse(name: &str) -> Option<TagMatch> {
+        match name {
+            "any" => Some(TagMatch::Any),
+            "all" => Some(TagMatch::All),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps notes having any (or all) of `tags` and none of `excluded_tags`, matching tags hierarchically.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TagFilter {
+    pub tags: Vec<String>,
+    pub excluded_tags: Vec<String>,
+    pub mode: TagMatch,
+}
+
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.excluded_tags.is_empty()
     }
 
-    // todo #943 save incrementally, don't overwrite whole file on every save
-    // idea: save offsets for every note and update just the changed note
-    let serialized_notes = serde_json::to_string(&notes).unwrap();
-    match std::fs::File::create(notes_directory.join("notes.json")) {
-        Ok(mut file) => {
-            if let Err(e) = file.write_all(serialized_notes.as_bytes()) {
-                eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error writing to file: {e}").as_str()));
-            } else {
-                println!("{}", print_utils::colorize(print_utils::Color::success(), "Note saved successfully."));
+    pub fn matches(&self, note: &RusticNote) -> bool {
+        let has_tags = match self.mode {
+            _ if self.tags.is_empty() => true,
+            TagMatch::Any => self.tags.iter().any(|tag| note.has_tag(tag)),
+            TagMatch::All => self.tags.iter().all(|tag| note.has_tag(tag)),
+        };
+        has_tags && !self.excluded_tags.iter().any(|tag| note.has_tag(tag))
+    }
+}
+
+/// A level of the tag hierarchy, counting the notes tagged with it or anything below it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TagNode {
+    pub note_count: usize,
+    pub children: BTreeMap<String, TagNode>,
+}
+
+impl TagNode {
+    pub fn build(notes: &[RusticNote]) -> TagNode {
+        let mut root = TagNode::default();
+        for note in notes {
+            // every level counts a note once, even when several of its tags are below that level
+            let mut paths: Vec<Vec<&str>> = vec![];
+            for tag in &note.tags {
+                let segments: Vec<&str> = tag.split('/').collect();
+                for depth in 1..=segments.len() {
+                    paths.push(segments[..depth].to_vec());
+                }
+            }
+            if paths.is_empty() {
+                continue;
+            }
+            paths.sort();
+            paths.dedup();
+
+            root.note_count += 1;
+            for path in paths {
+                let mut node = &mut root;
+                for segment in &path {
+                    node = node.children.entry(segment.to_string()).or_default();
+                }
+                node.note_count += 1;
             }
         }
-        Err(e) => {
-            eprintln!("{}", print_utils::colorize(print_utils::Color::error(), format!("Error creating file: {e}").as_str()));
-        }
+        root
     }
 }
 
-pub fn save_note(note: &RusticNote) {
-    println!("Saving note: {}", note.content);
+// a note that is yet to be given an id by the store
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewNote {
+    pub content: String,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+}
 
-    let note_json = serde_json::to_string(note).unwrap();
-    println!("Serialized note: {note_json}");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Revision {
+    pub number: u32,
+    pub updated_at: u64,
+}
 
-    let mut saved_notes: Vec<RusticNote> = load_all_notes();
+#[derive(Clone, Debug)]
+pub struct NoteRevision {
+    pub number: u32,
+    pub updated_at: u64,
+    pub content: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimestampField {
+    Created,
+    Updated,
+}
 
-    let existing_note = saved_notes.clone().into_iter().enumerate().find(|(_, n)| n.id == note.id);
-    saved_notes.push(note.clone());
+impl TimestampField {
+    pub fn parse(name: &str) -> Option<TimestampField> {
+        match name {
+            "created" => Some(TimestampField::Created),
+            "updated" => Some(TimestampField::Updated),
+            _ => None,
+        }
+    }
 
-    if existing_note.is_some() {
-        let last_index = saved_notes.len() - 1;
-        saved_notes.swap(existing_note.unwrap().0, last_index);
-        saved_notes.pop();
+    pub fn of(&self, note: &RusticNote) -> u64 {
+        match self {
+            TimestampField::Created => note.created_at,
+            TimestampField::Updated => note.updated_at,
+        }
     }
+}
 
-    save_notes(saved_notes);
+/// A collection of notes kept in a single directory.
+///
+/// Every note is stored as its own file under `notes/`, named after its id, while tags live in the
+/// append-only `manifest.log`. Operations report failures through their return value instead of
+/// printing, so the store can be used by other tools.
+///
+/// Whenever the content of a note changes, the previous content is kept under `history/<id>/`.
+///
+/// Content is searched through an inverted index kept in the append-only `search_index.log`, where
+/// every save only appends the terms of the notes it changed. Notes whose file no longer matches the
+/// size and modification time they were indexed with, such as ones edited by hand, are indexed again
+/// the next time the index is loaded.
+///
+/// All files are replaced atomically, and a snapshot of the store is kept in `backups/` at most once
+/// a day, with only the newest `backup_count` snapshots retained.
+#[derive(Clone, Debug)]
+pub struct NoteStore {
+    directory: PathBuf,
+    extension: String,
+    backup_count: usize,
 }
 
-pub fn load_all_notes() -> Vec<RusticNote> {
-    let config = config::get_config();
-    let notes_directory = Path::new(&config.notes_directory);
-    
-    if !notes_directory.exists() {
-        return vec![];
+impl NoteStore {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        NoteStore { directory: directory.into(), extension: String::from("md"), backup_count: 5 }
+    }
+
+    pub fn from_config(config: &RusticConfig) -> Self {
+        NoteStore::new(&config.notes_directory)
+            .with_extension(&config.note_extension)
+            .with_backup_count(config.backup_count)
+    }
+
+    pub fn with_extension(mut self, extension: &str) -> Self {
+        self.extension = extension.trim_start_matches('.').to_string();
+        self
+    }
+
+    pub fn with_backup_count(mut self, backup_count: usize) -> Self {
+        self.backup_count = backup_count;
+        self
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    pub fn backup_count(&self) -> usize {
+        self.backup_count
+    }
+
+    pub fn create(&self, content: &str, title: Option<&str>, tags: Vec<String>) -> Result<RusticNote> {
+        let new_note = NewNote { content: content.to_string(), title: title.map(|t| t.to_string()), tags };
+        Ok(self.create_many(vec![new_note])?.remove(0))
+    }
+
+    // everything is validated before the first note is written, and the store is backed up and
+    // indexed once for the whole batch
+    pub fn create_many(&self, new_notes: Vec<NewNote>) -> Result<Vec<RusticNote>> {
+        self.migrate_legacy_notes()?;
+        let first_id = self.note_ids()?.into_iter().max().unwrap_or(0) + 1;
+        let mut notes = vec![];
+        for (id, new_note) in (first_id..).zip(new_notes) {
+            for tag in &new_note.tags {
+                validate_tag(tag)?;
+            }
+            let mut note = RusticNote::new(id, new_note.content, new_note.tags);
+            note.title = new_note.title.as_deref().map(validate_title).transpose()?;
+            notes.push(note);
+        }
+
+        self.write_notes(&notes)?;
+        Ok(notes)
+    }
+
+    pub fn get(&self, id: u32) -> Result<RusticNote> {
+        self.migrate_legacy_notes()?;
+        let content = match self.read_note_file(&self.note_file(id)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(RusticError::NotFound(format!("Note with id {id}"))),
+            Err(e) => return Err(e.into()),
+        };
+        let metadata = Manifest::open(&self.directory)?.get(id)?;
+        Ok(note_from_parts(id, content, metadata.as_ref()))
+    }
+
+    // finds a note by its id or, for anything that is not a number, by its title ignoring case
+    pub fn find(&self, id_or_title: &str) -> Result<RusticNote> {
+        if let Ok(id) = id_or_title.parse::<u32>() {
+            return self.get(id);
+        }
+
+        let title = id_or_title.trim().to_lowercase();
+        let mut matching_notes: Vec<RusticNote> = self.list()?
+            .into_iter()
+            .filter(|note| note.title().is_some_and(|t| t.to_lowercase() == title))
+            .collect();
+        match matching_notes.len() {
+            0 => Err(RusticError::NotFound(format!("Note titled '{id_or_title}'"))),
+            1 => Ok(matching_notes.remove(0)),
+            _ => {
+                let ids: Vec<String> = matching_notes.iter().map(|note| note.id.to_string()).collect();
+                Err(RusticError::Usage(format!("Title '{id_or_title}' is ambiguous, it matches notes {}. Use the id instead.", ids.join(", "))))
+            },
+        }
     }
 
-    match std::fs::read_to_string(notes_directory.join("notes.json")) {
-        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|_| vec![]),
-        Err(_) => vec![],
+    pub fn update(&self, note: &RusticNote) -> Result<RusticNote> {
+        let previous_note = self.get(note.id)?;
+        let mut updated_note = note.clone();
+        updated_note.title = note.title.as_deref().map(validate_title).transpose()?;
+        updated_note.revisions = previous_note.revisions.clone();
+
+        if previous_note.content != note.content {
+            let number = previous_note.revision_number();
+            std::fs::create_dir_all(self.history_directory(note.id))?;
+            storage::write_atomically(&self.revision_file(note.id, number), format!("{}\n", previous_note.content).as_bytes())?;
+            updated_note.revisions.push(Revision { number, updated_at: previous_note.updated_at });
+        }
+
+        updated_note.updated_at = time_utils::now();
+        self.write_note(&updated_note)?;
+        Ok(updated_note)
+    }
+
+    pub fn delete(&self, id: u32) -> Result<RusticNote> {
+        let removed_note = self.get(id)?;
+        let mut manifest = Manifest::open(&self.directory)?;
+        // loaded while it still matches the notes on disk, so only an index that was already out of
+        // date gets rebuilt
+        let (mut search_log, mut index) = self.open_search_index()?;
+        self.backup_if_due()?;
+        storage::remove_durably(&self.note_file(id))?;
+        manifest.remove(id)?;
+        search_log.remove(&mut index, id)?;
+        if self.history_directory(id).exists() {
+            std::fs::remove_dir_all(self.history_directory(id))?;
+        }
+        Ok(removed_note)
+    }
+
+    // every revision of a note, oldest first and ending with the current content
+    pub fn history(&self, id: u32) -> Result<Vec<NoteRevision>> {
+        let note = self.get(id)?;
+        let mut revisions = vec![];
+        for revision in &note.revisions {
+            revisions.push(NoteRevision {
+                number: revision.number,
+                updated_at: revision.updated_at,
+                content: self.read_note_file(&self.revision_file(id, revision.number))?,
+            });
+        }
+        revisions.push(NoteRevision { number: note.revision_number(), updated_at: note.updated_at, content: note.content });
+        Ok(revisions)
+    }
+
+    pub fn revision(&self, id: u32, number: u32) -> Result<NoteRevision> {
+        self.history(id)?
+            .into_iter()
+            .find(|revision| revision.number == number)
+            .ok_or_else(|| RusticError::NotFound(format!("Revision {number} of note {id}")))
+    }
+
+    // restoring keeps the history intact, the restored content simply becomes the newest revision
+    pub fn restore(&self, id: u32, number: u32) -> Result<RusticNote> {
+        let revision = self.revision(id, number)?;
+        let mut note = self.get(id)?;
+        note.content = revision.content;
+        self.update(&note)
+    }
+
+    // tags describe a note without changing its content, so they leave the updated time and history alone
+    pub fn add_tags(&self, id: u32, tags: &[String]) -> Result<RusticNote> {
+        let mut note = self.get(id)?;
+        for tag in tags {
+            validate_tag(tag)?;
+            if !note.tags.contains(tag) {
+                note.tags.push(tag.clone());
+            }
+        }
+        self.write_metadata(&note)?;
+        Ok(note)
+    }
+
+    pub fn remove_tags(&self, id: u32, tags: &[String]) -> Result<RusticNote> {
+        let mut note = self.get(id)?;
+        if let Some(missing_tag) = tags.iter().find(|tag| !note.tags.contains(tag)) {
+            return Err(RusticError::NotFound(format!("Tag '{missing_tag}' on note {id}")));
+        }
+        note.tags.retain(|tag| !tags.contains(tag));
+        self.write_metadata(&note)?;
+        Ok(note)
+    }
+
+    // renames the tag and every tag below it on the notes having them, returns the ids of those notes
+    pub fn rename_tag(&self, tag: &str, new_tag: &str) -> Result<Vec<u32>> {
+        validate_tag(new_tag)?;
+        let mut renamed_ids = vec![];
+        for mut note in self.list()? {
+            if !note.has_tag(tag) {
+                continue;
+            }
+
+            let mut renamed_tags: Vec<String> = vec![];
+            for t in &note.tags {
+                let renamed_tag = match t.strip_prefix(tag) {
+                    Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{new_tag}{rest}"),
+                    _ => t.clone(),
+                };
+                if !renamed_tags.contains(&renamed_tag) {
+                    renamed_tags.push(renamed_tag);
+                }
+            }
+            note.tags = renamed_tags;
+            self.write_metadata(&note)?;
+            renamed_ids.push(note.id);
+        }
+
+        if renamed_ids.is_empty() {
+            return Err(RusticError::NotFound(format!("Tag '{tag}'")));
+        }
+        Ok(renamed_ids)
+    }
+
+    // links are resolved against the current titles, so the graph is always built from the notes themselves
+    pub fn link_graph(&self) -> Result<LinkGraph> {
+        Ok(LinkGraph::build(&self.list()?))
+    }
+
+    // every tag in use together with the number of notes having it
+    pub fn tags(&self) -> Result<BTreeMap<String, usize>> {
+        let mut tags = BTreeMap::new();
+        for note in self.list()? {
+            for tag in note.tags {
+                *tags.entry(tag).or_insert(0) += 1;
+            }
+        }
+        Ok(tags)
+    }
+
+    pub fn list(&self) -> Result<Vec<RusticNote>> {
+        self.migrate_legacy_notes()?;
+
+        let mut ids = self.note_ids()?;
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+        ids.sort();
+
+        let manifest_entries = Manifest::open(&self.directory)?.entries()?;
+        let mut notes = vec![];
+        for id in ids {
+            notes.push(note_from_parts(id, self.read_note_file(&self.note_file(id))?, manifest_entries.get(&id)));
+        }
+        Ok(notes)
+    }
+
+    // notes matching a query in the language of `Query`, best match first
+    pub fn search(&self, query: &str) -> Result<Vec<RusticNote>> {
+        let query = Query::parse(query)?;
+        let index = self.search_index()?;
+        Ok(query.rank(query.filter(self.list()?, &index), &index))
+    }
+
+    pub fn search_index(&self) -> Result<SearchIndex> {
+        Ok(self.open_search_index()?.1)
+    }
+
+    pub fn reindex(&self) -> Result<SearchIndex> {
+        std::fs::create_dir_all(&self.directory)?;
+        let (mut search_log, _) = SearchLog::open(&self.directory)?;
+        let mut index = SearchIndex::default();
+        for note in self.list()? {
+            index.add(note.id, &note.content, file_fingerprint(&self.note_file(note.id))?);
+        }
+        search_log.compact(&index)?;
+        Ok(index)
+    }
+
+    pub fn backup(&self) -> Result<PathBuf> {
+        Ok(storage::snapshot(&self.directory, &BACKUP_ENTRIES, self.backup_count.max(1))?)
+    }
+
+    pub fn backups(&self) -> Result<Vec<PathBuf>> {
+        Ok(storage::list_snapshots(&self.directory)?)
+    }
+
+    fn backup_if_due(&self) -> Result<()> {
+        if self.backup_count == 0 || !Manifest::exists(&self.directory) {
+            return Ok(());
+        }
+
+        let last_backup_time = self.backups()?.last().map(|snapshot| storage::snapshot_time(snapshot).0).unwrap_or(0);
+        if time_utils::now().saturating_sub(last_backup_time) >= BACKUP_INTERVAL_SECS {
+            self.backup()?;
+        }
+        Ok(())
+    }
+
+    fn notes_directory(&self) -> PathBuf {
+        self.directory.join("notes")
+    }
+
+    fn note_file(&self, id: u32) -> PathBuf {
+        self.notes_directory().join(format!("{id}.{}", self.extension))
+    }
+
+    fn history_directory(&self, id: u32) -> PathBuf {
+        self.directory.join("history").join(id.to_string())
+    }
+
+    fn revision_file(&self, id: u32, number: u32) -> PathBuf {
+        self.history_directory(id).join(format!("{number}.{}", self.extension))
+    }
+
+    // the index only speeds searching up, so notes that changed since they were indexed, by hand or
+    // by a crash before the index was saved, are simply indexed again
+    fn open_search_index(&self) -> Result<(SearchLog, SearchIndex)> {
+        self.migrate_legacy_notes()?;
+        let (mut search_log, mut index) = SearchLog::open(&self.directory)?;
+        let note_ids: BTreeSet<u32> = self.note_ids()?.into_iter().collect();
+        let removed_ids: Vec<u32> = index.ids().difference(&note_ids).copied().collect();
+        for id in removed_ids {
+            search_log.remove(&mut index, id)?;
+        }
+
+        for id in note_ids {
+            let fingerprint = file_fingerprint(&self.note_file(id))?;
+            if index.fingerprint(id) != Some(fingerprint) {
+                search_log.put(&mut index, id, &self.read_note_file(&self.note_file(id))?, fingerprint)?;
+            }
+        }
+        Ok((search_log, index))
+    }
+
+    fn legacy_notes_file(&self) -> PathBuf {
+        self.directory.join("notes.json")
+    }
+
+    // files are the source of truth for which notes exist, the manifest only adds metadata to them
+    fn note_ids(&self) -> Result<Vec<u32>> {
+        let entries = match std::fs::read_dir(self.notes_directory()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut ids = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(self.extension.as_str()) {
+                continue;
+            }
+            if let Some(id) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse::<u32>().ok()) {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn read_note_file(&self, path: &Path) -> io::Result<String> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(content.strip_suffix('\n').unwrap_or(&content).to_string())
+    }
+
+    fn write_note(&self, note: &RusticNote) -> Result<()> {
+        self.write_notes(std::slice::from_ref(note))
+    }
+
+    fn write_notes(&self, notes: &[RusticNote]) -> Result<()> {
+        let mut manifest = Manifest::open(&self.directory)?;
+        // loaded before any note file changes, otherwise the new content would be indexed twice
+        let (mut search_log, mut index) = self.open_search_index()?;
+        self.backup_if_due()?;
+        std::fs::create_dir_all(self.notes_directory())?;
+        for note in notes {
+            storage::write_atomically(&self.note_file(note.id), format!("{}\n", note.content).as_bytes())?;
+            manifest.put(&note.metadata())?;
+        }
+
+        for note in notes {
+            search_log.put(&mut index, note.id, &note.content, file_fingerprint(&self.note_file(note.id))?)?;
+        }
+        Ok(())
+    }
+
+    fn write_metadata(&self, note: &RusticNote) -> Result<()> {
+        let mut manifest = Manifest::open(&self.directory)?;
+        self.backup_if_due()?;
+        manifest.put(&note.metadata())
+    }
+
+    // stores from before notes were kept as separate files have everything in a single notes.json
+    fn migrate_legacy_notes(&self) -> Result<()> {
+        let legacy_notes_file = self.legacy_notes_file();
+        if !legacy_notes_file.exists() || Manifest::exists(&self.directory) {
+            return Ok(());
+        }
+
+        let legacy_notes: Vec<RusticNote> = serde_json::from_str(&std::fs::read_to_string(&legacy_notes_file)?)?;
+        std::fs::create_dir_all(self.notes_directory())?;
+        let mut manifest = Manifest::open(&self.directory)?;
+        for note in &legacy_notes {
+            storage::write_atomically(&self.note_file(note.id), format!("{}\n", note.content).as_bytes())?;
+            manifest.put(&note.metadata())?;
+        }
+        Ok(std::fs::rename(&legacy_notes_file, legacy_notes_file.with_extension("json.migrated"))?)
+    }
+}
+
+fn note_from_parts(id: u32, content: String, metadata: Option<&NoteMetadata>) -> RusticNote {
+    match metadata {
+        Some(metadata) => RusticNote {
+            id,
+            content,
+            tags: metadata.tags.clone(),
+            created_at: metadata.created_at,
+            updated_at: metadata.updated_at,
+            revisions: metadata.revisions.clone(),
+            title: metadata.title.clone(),
+        },
+        None => RusticNote { id, content, tags: vec![], created_at: 0, updated_at: 0, revisions: vec![], title: None },
     }
 }
 
-pub fn get_note_by_id(id: u32) -> Option<RusticNote> {
-    let notes = load_all_notes();
-    notes.into_iter().find(|note| note.id == id)
+fn file_fingerprint(path: &Path) -> io::Result<Fingerprint> {
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok(Fingerprint { length: metadata.len(), modified_secs: modified.as_secs(), modified_nanos: modified.subsec_nanos() })
 }
 
-pub fn remove_note_by_id(id: u32) {
-    let mut updated_notes = load_all_notes();
-    updated_notes.retain(|note| note.id != id);
-    save_notes(updated_notes);
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    tag.strip_prefix(filter).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
 }
 
-pub fn get_next_id() -> u32 {
-    let notes = load_all_notes();
-    let mut biggest_id = 0;
-    for note in notes {
-        if note.id > biggest_id {
-            biggest_id = note.id;
-        }
+fn validate_title(title: &str) -> Result<String> {
+    let title = title.trim();
+    if title.is_empty() || title.contains('\n') {
+        return Err(RusticError::Usage("Title cannot be empty or span several lines.".to_string()));
     }
-    biggest_id + 1
+    Ok(title.to_string())
 }
 
-// realistically, i should use something like ripgrep here, read up on Boyer–Moore string search algo and maybe implement it?
-pub fn slow_search(notes: &[RusticNote], query: &str) -> Vec<RusticNote> {
-    notes.iter()
-        .filter(|&note| note.content.contains(query))
-        .cloned()
-        .collect()
+fn validate_tag(tag: &str) -> Result<()> {
+    if tag.split('/').any(|segment| segment.is_empty()) || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(RusticError::Usage(format!("Invalid tag: '{tag}', tags cannot be empty or contain spaces and commas, and levels are separated by a single '/'.")));
+    }
+    Ok(())
 }
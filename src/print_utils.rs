@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 pub struct Color {
     red: u8,
     green: u8,
@@ -53,3 +55,19 @@ pub fn colorize(color: Color, input: &str) -> String {
 pub fn bg_colorize(color: Color, input: &str) -> String {
     format!("\x1b[48;2;{};{};{}m", color.red, color.green, color.blue) + input + "\x1b[0m"
 }
+
+// colorizes the given byte ranges of the input, ranges have to be sorted and fall on char boundaries
+pub fn highlight(input: &str, ranges: &[Range<usize>]) -> String {
+    let mut output = String::new();
+    let mut position = 0;
+    for range in ranges {
+        if range.start < position {
+            continue;
+        }
+        output.push_str(&input[position..range.start]);
+        output.push_str(&colorize(Color::warning(), &input[range.clone()]));
+        position = range.end;
+    }
+    output.push_str(&input[position..]);
+    output
+}
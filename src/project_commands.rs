@@ -0,0 +1,149 @@
+use crate::cli_command::{ArgumentType, CliCommandArgument, CliCommandBuilder, CliCommand, CliCommandOption, ParsedArgs};
+use crate::print_utils;
+use rustic_notes::{config, Result, RusticError, Workspace};
+
+pub fn build_project_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("project")
+        .set_description("Manage projects, each holding its own set of notes")
+        .add_subcommand(&build_project_new_command())
+        .add_subcommand(&build_project_list_command())
+        .add_subcommand(&build_project_switch_command())
+        .add_subcommand(&build_project_rename_command())
+        .add_subcommand(&build_project_delete_command())
+        .build()
+}
+
+fn build_project_new_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("new")
+        .set_description("Create a new project")
+        .add_argument(
+            &CliCommandArgument {
+                name: "name".to_string(),
+                description: Some("Name of the project".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let name: String = args.get_required("name")?;
+            open_workspace()?.create(&name)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Project '{name}' created.").as_str()));
+            Ok(())
+        }).build()
+}
+
+fn build_project_list_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("list")
+        .add_alias("ls")
+        .set_description("List all projects, marking the active one")
+        .set_action(|_args: ParsedArgs| {
+            let workspace = open_workspace()?;
+            let active_project = workspace.active_project()?;
+
+            println!("Projects:");
+            for project in workspace.projects()? {
+                if project == active_project {
+                    println!("{}", print_utils::colorize(print_utils::Color::success(), format!("* {project}").as_str()));
+                } else {
+                    println!("  {project}");
+                }
+            }
+            Ok(())
+        }).build()
+}
+
+fn build_project_switch_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("switch")
+        .set_description("Make a project the active one for all following commands")
+        .add_argument(
+            &CliCommandArgument {
+                name: "name".to_string(),
+                description: Some("Name of the project".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let name: String = args.get_required("name")?;
+            open_workspace()?.switch(&name)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Switched to project '{name}'.").as_str()));
+            Ok(())
+        }).build()
+}
+
+fn build_project_rename_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("rename")
+        .set_description("Rename a project")
+        .add_argument(
+            &CliCommandArgument {
+                name: "name".to_string(),
+                description: Some("Name of the project".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .add_argument(
+            &CliCommandArgument {
+                name: "new_name".to_string(),
+                description: Some("New name of the project".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let name: String = args.get_required("name")?;
+            let new_name: String = args.get_required("new_name")?;
+            open_workspace()?.rename(&name, &new_name)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Project '{name}' renamed to '{new_name}'.").as_str()));
+            Ok(())
+        }).build()
+}
+
+fn build_project_delete_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("delete")
+        .add_alias("rm")
+        .set_description("Delete a project together with all of its notes")
+        .add_argument(
+            &CliCommandArgument {
+                name: "name".to_string(),
+                description: Some("Name of the project".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .add_option(
+            &CliCommandOption {
+                name: "force".to_string(),
+                short_name: Some("f".to_string()),
+                description: Some("Delete the project even if it still has notes".to_string()),
+                is_flag: true,
+                ..Default::default()
+            }
+        ).set_action(|args: ParsedArgs| {
+            let name: String = args.get_required("name")?;
+            let workspace = open_workspace()?;
+            let note_count = workspace.open(&name)?.list()?.len();
+            if note_count > 0 && !args.flag("force") {
+                return Err(RusticError::Usage(format!("Project '{name}' still has {note_count} notes, pass --force to delete it anyway.")));
+            }
+
+            workspace.delete(&name)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Project '{name}' deleted.").as_str()));
+            Ok(())
+        }).build()
+}
+
+fn open_workspace() -> Result<Workspace> {
+    Ok(Workspace::from_config(&config::get_config()?))
+}
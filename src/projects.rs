@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+use serde::{Serialize, Deserialize};
+use crate::config::RusticConfig;
+use crate::error::{Result, RusticError};
+use crate::notes::NoteStore;
+use crate::storage;
+
+pub const DEFAULT_PROJECT: &str = "default";
+
+#[derive(Serialize, Deserialize, Default)]
+struct WorkspaceState {
+    active_project: Option<String>,
+}
+
+/// Named projects sharing one notes directory.
+///
+/// The default project is stored directly in the root directory, which keeps stores created before
+/// projects existed working, while every other project is a separate store under `projects/<name>`.
+/// The active project is remembered in `state.toml`.
+#[derive(Clone, Debug)]
+pub struct Workspace {
+    root: PathBuf,
+    extension: String,
+    backup_count: usize,
+}
+
+impl Workspace {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        let store = NoteStore::new("");
+        Workspace { root: root.into(), extension: store.extension().to_string(), backup_count: store.backup_count() }
+    }
+
+    pub fn from_config(config: &RusticConfig) -> Self {
+        Workspace {
+            root: PathBuf::from(&config.notes_directory),
+            extension: config.note_extension.clone(),
+            backup_count: config.backup_count,
+        }
+    }
+
+    pub fn projects(&self) -> Result<Vec<String>> {
+        let mut projects = vec![DEFAULT_PROJECT.to_string()];
+        let entries = match std::fs::read_dir(self.root.join("projects")) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(projects),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut named_projects = vec![];
+        for entry in entries {
+            let entry = entry?;
+            if entry.path().is_dir() {
+                named_projects.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        named_projects.sort();
+        projects.extend(named_projects);
+        Ok(projects)
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        name == DEFAULT_PROJECT || self.project_directory(name).is_dir()
+    }
+
+    pub fn open(&self, name: &str) -> Result<NoteStore> {
+        if !self.exists(name) {
+            return Err(RusticError::NotFound(format!("Project '{name}'")));
+        }
+        Ok(self.store(name))
+    }
+
+    pub fn open_active(&self) -> Result<NoteStore> {
+        self.open(&self.active_project()?)
+    }
+
+    pub fn create(&self, name: &str) -> Result<NoteStore> {
+        validate_project_name(name)?;
+        if self.exists(name) {
+            return Err(RusticError::Usage(format!("Project '{name}' already exists.")));
+        }
+        std::fs::create_dir_all(self.project_directory(name))?;
+        Ok(self.store(name))
+    }
+
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<()> {
+        if name == DEFAULT_PROJECT {
+            return Err(RusticError::Usage(format!("The {DEFAULT_PROJECT} project cannot be renamed.")));
+        }
+        validate_project_name(new_name)?;
+        self.open(name)?;
+        if self.exists(new_name) {
+            return Err(RusticError::Usage(format!("Project '{new_name}' already exists.")));
+        }
+
+        let was_active = self.active_project()? == name;
+        std::fs::rename(self.project_directory(name), self.project_directory(new_name))?;
+        if was_active {
+            self.switch(new_name)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        if name == DEFAULT_PROJECT {
+            return Err(RusticError::Usage(format!("The {DEFAULT_PROJECT} project cannot be deleted.")));
+        }
+        self.open(name)?;
+
+        let was_active = self.active_project()? == name;
+        std::fs::remove_dir_all(self.project_directory(name))?;
+        if was_active {
+            self.switch(DEFAULT_PROJECT)?;
+        }
+        Ok(())
+    }
+
+    pub fn active_project(&self) -> Result<String> {
+        let state = self.load_state()?;
+        // a project removed by hand falls back to the default one
+        match state.active_project {
+            Some(name) if self.exists(&name) => Ok(name),
+            _ => Ok(DEFAULT_PROJECT.to_string()),
+        }
+    }
+
+    pub fn switch(&self, name: &str) -> Result<()> {
+        self.open(name)?;
+        let state = WorkspaceState { active_project: Some(name.to_string()) };
+        let serialized_state = toml::to_string_pretty(&state).map_err(|e| RusticError::Parse(e.to_string()))?;
+        std::fs::create_dir_all(&self.root)?;
+        Ok(storage::write_atomically(&self.state_file(), serialized_state.as_bytes())?)
+    }
+
+    fn store(&self, name: &str) -> NoteStore {
+        let directory = if name == DEFAULT_PROJECT { self.root.clone() } else { self.project_directory(name) };
+        NoteStore::new(directory)
+            .with_extension(&self.extension)
+            .with_backup_count(self.backup_count)
+    }
+
+    fn project_directory(&self, name: &str) -> PathBuf {
+        self.root.join("projects").join(name)
+    }
+
+    fn state_file(&self) -> PathBuf {
+        self.root.join("state.toml")
+    }
+
+    fn load_state(&self) -> Result<WorkspaceState> {
+        match std::fs::read_to_string(self.state_file()) {
+            Ok(data) => toml::from_str(&data).map_err(|e| RusticError::Parse(format!("{}: {e}", self.state_file().display()))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(WorkspaceState::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn validate_project_name(name: &str) -> Result<()> {
+    let is_valid = !name.is_empty()
+        && name != DEFAULT_PROJECT
+        && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    if is_valid {
+        Ok(())
+    } else {
+        Err(RusticError::Usage(format!("Invalid project name: '{name}', use letters, digits, '-' and '_' only.")))
+    }
+}
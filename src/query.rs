@@ -0,0 +1,375 @@
+use std::collections::BTreeSet;
+use crate::error::{Result, RusticError};
+use crate::notes::{RusticNote, TimestampField};
+use crate::search::{self, QueryItem, SearchIndex, SearchQuery};
+use crate::time_utils;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Before,
+    BeforeOrOn,
+    On,
+    OnOrAfter,
+    After,
+}
+
+impl Comparison {
+    // dates cover a span of time, `>` means after the whole span and `<` before any of it
+    fn matches(&self, timestamp: u64, (start, end): (u64, u64)) -> bool {
+        match self {
+            Comparison::Before => timestamp < start,
+            Comparison::BeforeOrOn => timestamp < end,
+            Comparison::On => start <= timestamp && timestamp < end,
+            Comparison::OnOrAfter => timestamp >= start,
+            Comparison::After => timestamp >= end,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    Text(QueryItem),
+    Tag(String),
+    Date(TimestampField, Comparison, (u64, u64)),
+    Not(Box<Filter>),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+}
+
+/// Filter expression over notes, e.g. `tag:work AND (deploy OR rollback) -tag:archived created:>2026-01-01`.
+///
+/// Words are matched against the content through the search index, terms next to each other are
+/// AND-ed, `OR` binds looser than `AND`, and `-` or `NOT` negates the term after it. The fields
+/// `tag:`, `created:` and `updated:` filter on metadata, dates accept `<`, `<=`, `=`, `>=` and `>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    pub filter: Filter,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Query> {
+        let tokens = lex(query)?;
+        let mut parser = Parser { query, tokens, position: 0 };
+        if parser.tokens.is_empty() {
+            return Err(RusticError::Usage("Query is empty.".to_string()));
+        }
+
+        let filter = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error_at(token, &format!("Unexpected '{}'", token.text(query))));
+        }
+        Ok(Query { filter })
+    }
+
+    pub fn matching_ids(&self, notes: &[RusticNote], index: &SearchIndex) -> BTreeSet<u32> {
+        let all_ids = notes.iter().map(|note| note.id).collect();
+        evaluate(&self.filter, notes, index, &all_ids)
+    }
+
+    // keeps the order of the notes
+    pub fn filter(&self, mut notes: Vec<RusticNote>, index: &SearchIndex) -> Vec<RusticNote> {
+        let matching_ids = self.matching_ids(&notes, index);
+        notes.retain(|note| matching_ids.contains(&note.id));
+        notes
+    }
+
+    // best text matches first, queries without any words keep the order of the notes
+    pub fn rank(&self, mut notes: Vec<RusticNote>, index: &SearchIndex) -> Vec<RusticNote> {
+        if let Some(text_query) = self.text_query() {
+            notes.sort_by(|a, b| index.score(b.id, &text_query).total_cmp(&index.score(a.id, &text_query)).then(a.id.cmp(&b.id)));
+        }
+        notes
+    }
+
+    pub fn highlight_ranges(&self, text: &str) -> Vec<std::ops::Range<usize>> {
+        self.text_query().map_or(vec![], |text_query| search::highlight_ranges(text, &text_query))
+    }
+
+    // the words a note is looked for by, ignoring negated ones
+    fn text_query(&self) -> Option<SearchQuery> {
+        let mut items = vec![];
+        collect_text_items(&self.filter, &mut items);
+        if items.is_empty() {
+            None
+        } else {
+            Some(SearchQuery { clauses: vec![items] })
+        }
+    }
+}
+
+fn evaluate(filter: &Filter, notes: &[RusticNote], index: &SearchIndex, all_ids: &BTreeSet<u32>) -> BTreeSet<u32> {
+    match filter {
+        Filter::Text(item) => index.matching_ids(item).intersection(all_ids).copied().collect(),
+        Filter::Tag(tag) => notes.iter().filter(|note| note.has_tag(tag)).map(|note| note.id).collect(),
+        Filter::Date(field, comparison, span) => notes.iter()
+            .filter(|note| comparison.matches(field.of(note), *span))
+            .map(|note| note.id)
+            .collect(),
+        Filter::Not(inner) => all_ids.difference(&evaluate(inner, notes, index, all_ids)).copied().collect(),
+        Filter::And(filters) => filters.iter()
+            .map(|filter| evaluate(filter, notes, index, all_ids))
+            .reduce(|a, b| a.intersection(&b).copied().collect())
+            .unwrap_or_default(),
+        Filter::Or(filters) => filters.iter()
+            .flat_map(|filter| evaluate(filter, notes, index, all_ids))
+            .collect(),
+    }
+}
+
+fn collect_text_items(filter: &Filter, items: &mut Vec<QueryItem>) {
+    match filter {
+        Filter::Text(item) => items.push(item.clone()),
+        Filter::And(filters) | Filter::Or(filters) => filters.iter().for_each(|filter| collect_text_items(filter, items)),
+        Filter::Tag(_) | Filter::Date(..) | Filter::Not(_) => {},
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Phrase(String),
+    OpenParen,
+    CloseParen,
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+impl Token {
+    fn text<'a>(&self, query: &'a str) -> &'a str {
+        &query[self.start..self.end]
+    }
+}
+
+fn lex(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = query.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let kind = match c {
+            '(' | ')' => {
+                chars.next();
+                if c == '(' { TokenKind::OpenParen } else { TokenKind::CloseParen }
+            },
+            '"' => {
+                chars.next();
+                let Some((end, _)) = chars.find(|&(_, c)| c == '"') else {
+                    return Err(syntax_error(query, start, query.len(), "Unclosed '\"'"));
+                };
+                tokens.push(Token { kind: TokenKind::Phrase(query[start + 1..end].to_string()), start, end: end + 1 });
+                continue;
+            },
+            // only a leading dash negates, `well-known` is a single word
+            '-' if chars.clone().nth(1).is_some_and(|(_, next)| !next.is_whitespace()) => {
+                chars.next();
+                TokenKind::Not
+            },
+            _ => {
+                let mut end = query.len();
+                while let Some(&(index, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        end = index;
+                        break;
+                    }
+                    chars.next();
+                }
+                match &query[start..end] {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    word => TokenKind::Word(word.to_string()),
+                }
+            },
+        };
+        let end = chars.peek().map_or(query.len(), |&(index, _)| index);
+        tokens.push(Token { kind, start, end });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Filter> {
+        let mut filters = vec![self.parse_and()?];
+        while self.peek().is_some_and(|token| token.kind == TokenKind::Or) {
+            self.next();
+            filters.push(self.parse_and()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::Or(filters) })
+    }
+
+    fn parse_and(&mut self) -> Result<Filter> {
+        let mut filters = vec![self.parse_unary()?];
+        loop {
+            match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::And) => {
+                    self.next();
+                    filters.push(self.parse_unary()?);
+                },
+                Some(TokenKind::Or | TokenKind::CloseParen) | None => break,
+                Some(_) => filters.push(self.parse_unary()?),
+            }
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::And(filters) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter> {
+        let Some(token) = self.next() else {
+            return Err(syntax_error(self.query, self.query.len(), self.query.len(), "Expected a search term but the query ended"));
+        };
+
+        match token.kind {
+            TokenKind::Not => Ok(Filter::Not(Box::new(self.parse_unary()?))),
+            TokenKind::OpenParen => {
+                let filter = self.parse_or()?;
+                match self.next() {
+                    Some(Token { kind: TokenKind::CloseParen, .. }) => Ok(filter),
+                    Some(unexpected) => Err(self.error_at(&unexpected, &format!("Expected ')' but found '{}'", unexpected.text(self.query)))),
+                    None => Err(self.error_at(&token, "Unclosed '('")),
+                }
+            },
+            TokenKind::Phrase(ref phrase) => {
+                let terms: Vec<String> = search::tokenize(phrase).into_iter().map(|(term, _)| term).collect();
+                if terms.is_empty() {
+                    return Err(self.error_at(&token, "Phrase has no words to search for"));
+                }
+                Ok(Filter::Text(QueryItem::Phrase(terms)))
+            },
+            TokenKind::Word(ref word) => self.parse_word(&token, word),
+            TokenKind::CloseParen | TokenKind::And | TokenKind::Or => {
+                Err(self.error_at(&token, &format!("Expected a search term but found '{}'", token.text(self.query))))
+            },
+        }
+    }
+
+    fn parse_word(&self, token: &Token, word: &str) -> Result<Filter> {
+        if let Some((field, value)) = word.split_once(':') {
+            return match field {
+                "tag" if !value.is_empty() => Ok(Filter::Tag(value.to_string())),
+                "tag" => Err(self.error_at(token, "Missing tag after 'tag:'")),
+                "created" | "updated" => {
+                    let (comparison, date) = parse_comparison(value);
+                    let span = time_utils::parse_date_span(date).map_err(|e| self.error_at(token, e.to_string().trim_end_matches('.')))?;
+                    Ok(Filter::Date(TimestampField::parse(field).unwrap(), comparison, span))
+                },
+                _ => Err(self.error_at(token, &format!("Unknown field '{field}', expected tag, created or updated"))),
+            };
+        }
+
+        let is_prefix = word.ends_with('*');
+        let mut filters: Vec<Filter> = search::tokenize(word).into_iter()
+            .map(|(term, _)| Filter::Text(if is_prefix { QueryItem::Prefix(term) } else { QueryItem::Term(term) }))
+            .collect();
+        match filters.len() {
+            0 => Err(self.error_at(token, &format!("'{word}' has no words to search for"))),
+            1 => Ok(filters.remove(0)),
+            _ => Ok(Filter::And(filters)),
+        }
+    }
+
+    fn error_at(&self, token: &Token, message: &str) -> RusticError {
+        syntax_error(self.query, token.start, token.end, message)
+    }
+}
+
+fn parse_comparison(value: &str) -> (Comparison, &str) {
+    for (operator, comparison) in [(">=", Comparison::OnOrAfter), ("<=", Comparison::BeforeOrOn), (">", Comparison::After), ("<", Comparison::Before), ("=", Comparison::On)] {
+        if let Some(date) = value.strip_prefix(operator) {
+            return (comparison, date);
+        }
+    }
+    (Comparison::On, value)
+}
+
+// points at the offending part of the query, the same way compilers do
+fn syntax_error(query: &str, start: usize, end: usize, message: &str) -> RusticError {
+    let column = query[..start].chars().count();
+    let width = query[start..end].chars().count().max(1);
+    RusticError::Usage(format!(
+        "{message} at position {}:\n    {query}\n    {}{}",
+        column + 1,
+        " ".repeat(column),
+        "^".repeat(width)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(word: &str) -> Filter {
+        Filter::Text(QueryItem::Term(word.to_string()))
+    }
+
+    fn parse(query: &str) -> Filter {
+        Query::parse(query).unwrap().filter
+    }
+
+    fn error(query: &str) -> String {
+        Query::parse(query).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn or_binds_looser_than_and() {
+        assert_eq!(parse("a b OR c"), Filter::Or(vec![Filter::And(vec![term("a"), term("b")]), term("c")]));
+        assert_eq!(parse("a AND (b OR c)"), Filter::And(vec![term("a"), Filter::Or(vec![term("b"), term("c")])]));
+    }
+
+    #[test]
+    fn negates_the_term_after_a_dash_or_not() {
+        assert_eq!(parse("-a NOT b"), Filter::And(vec![Filter::Not(Box::new(term("a"))), Filter::Not(Box::new(term("b")))]));
+        assert_eq!(parse("well-known"), Filter::And(vec![term("well"), term("known")]));
+        assert_eq!(parse("-(a OR b)"), Filter::Not(Box::new(Filter::Or(vec![term("a"), term("b")]))));
+    }
+
+    #[test]
+    fn parses_fields_prefixes_and_phrases() {
+        let (start, end) = time_utils::parse_date_span("2026-01-01").unwrap();
+        assert_eq!(
+            parse("tag:work created:>2026-01-01 deploy* \"Hot Fix\""),
+            Filter::And(vec![
+                Filter::Tag("work".to_string()),
+                Filter::Date(TimestampField::Created, Comparison::After, (start, end)),
+                Filter::Text(QueryItem::Prefix("deploy".to_string())),
+                Filter::Text(QueryItem::Phrase(vec!["hot".to_string(), "fix".to_string()])),
+            ])
+        );
+        assert_eq!(parse("updated:2026-01-01"), Filter::Date(TimestampField::Updated, Comparison::On, (start, end)));
+    }
+
+    #[test]
+    fn points_at_the_offending_part_of_the_query() {
+        assert_eq!(error("a (b OR c"), "Unclosed '(' at position 3:\n    a (b OR c\n      ^");
+        assert_eq!(error("a colour:red"), "Unknown field 'colour', expected tag, created or updated at position 3:\n    a colour:red\n      ^^^^^^^^^^");
+        assert_eq!(error("a OR"), "Expected a search term but the query ended at position 5:\n    a OR\n        ^");
+        assert_eq!(error("a \"b c"), "Unclosed '\"' at position 3:\n    a \"b c\n      ^^^^");
+        assert_eq!(error("  "), "Query is empty.");
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+use regex::Regex;
+use serde::{Serialize, Deserialize};
+
+// standard BM25 tuning
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryItem {
+    Term(String),
+    // `term*`, matches every token starting with the term
+    Prefix(String),
+    // `"several terms"`, matches the terms next to each other in this order
+    Phrase(Vec<String>),
+}
+
+/// Full-text query as an OR of clauses, each matching notes that contain every one of its items.
+/// Matching is case-insensitive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchQuery {
+    pub clauses: Vec<Vec<QueryItem>>,
+}
+
+impl SearchQuery {
+    fn items(&self) -> impl Iterator<Item = &QueryItem> {
+        self.clauses.iter().flatten()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit {
+    pub id: u32,
+    pub score: f64,
+}
+
+/// Size and modification time of the file a note was indexed from, the note has to be indexed again
+/// once they change.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct Fingerprint {
+    pub length: u64,
+    pub modified_secs: u64,
+    pub modified_nanos: u32,
+}
+
+// what the index knows about a single note, its terms are kept so removing the note only has to
+// touch their postings
+#[derive(Debug)]
+struct IndexedNote {
+    fingerprint: Fingerprint,
+    length: u32,
+    terms: Vec<String>,
+}
+
+/// Inverted index over the content of notes, mapping every lowercase token to the notes and
+/// positions it appears at.
+#[derive(Default, Debug)]
+pub struct SearchIndex {
+    postings: BTreeMap<String, BTreeMap<u32, Vec<u32>>>,
+    notes: BTreeMap<u32, IndexedNote>,
+}
+
+impl SearchIndex {
+    pub fn ids(&self) -> BTreeSet<u32> {
+        self.notes.keys().copied().collect()
+    }
+
+    pub fn fingerprint(&self, id: u32) -> Option<Fingerprint> {
+        self.notes.get(&id).map(|note| note.fingerprint)
+    }
+
+    pub fn add(&mut self, id: u32, content: &str, fingerprint: Fingerprint) {
+        let mut terms: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for (position, (token, _)) in tokenize(content).into_iter().enumerate() {
+            terms.entry(token).or_default().push(position as u32);
+        }
+        self.insert(id, fingerprint, terms);
+    }
+
+    // adds a note from the positions of each of its terms, as returned by `terms`
+    pub fn insert(&mut self, id: u32, fingerprint: Fingerprint, terms: BTreeMap<String, Vec<u32>>) {
+        self.remove(id);
+
+        let length = terms.values().map(|positions| positions.len() as u32).sum();
+        let note_terms = terms.keys().cloned().collect();
+        for (term, positions) in terms {
+            self.postings.entry(term).or_default().insert(id, positions);
+        }
+        self.notes.insert(id, IndexedNote { fingerprint, length, terms: note_terms });
+    }
+
+    pub fn terms(&self, id: u32) -> BTreeMap<String, Vec<u32>> {
+        let Some(note) = self.notes.get(&id) else {
+            return BTreeMap::new();
+        };
+        note.terms.iter()
+            .filter_map(|term| self.postings.get(term).and_then(|documents| documents.get(&id)).map(|positions| (term.clone(), positions.clone())))
+            .collect()
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        let Some(note) = self.notes.remove(&id) else {
+            return;
+        };
+        for term in note.terms {
+            if let Some(documents) = self.postings.get_mut(&term) {
+                documents.remove(&id);
+                if documents.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    // matching notes, best match first
+    pub fn search(&self, query: &SearchQuery) -> Vec<SearchHit> {
+        let mut matching_ids = BTreeSet::new();
+        for clause in &query.clauses {
+            let mut clause_ids: Option<BTreeSet<u32>> = None;
+            for item in clause {
+                let item_ids = self.matching_ids(item);
+                clause_ids = Some(match clause_ids {
+                    Some(ids) => ids.intersection(&item_ids).copied().collect(),
+                    None => item_ids,
+                });
+            }
+            matching_ids.extend(clause_ids.unwrap_or_default());
+        }
+
+        let mut hits: Vec<SearchHit> = matching_ids.into_iter()
+            .map(|id| SearchHit { id, score: self.score(id, query) })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)));
+        hits
+    }
+
+    pub fn matching_ids(&self, item: &QueryItem) -> BTreeSet<u32> {
+        match item {
+            QueryItem::Term(term) => self.postings.get(term).map(|documents| documents.keys().copied().collect()).unwrap_or_default(),
+            QueryItem::Prefix(prefix) => self.expand_prefix(prefix)
+                .flat_map(|(_, documents)| documents.keys().copied())
+                .collect(),
+            QueryItem::Phrase(terms) => self.phrase_ids(terms),
+        }
+    }
+
+    fn expand_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a String, &'a BTreeMap<u32, Vec<u32>>)> {
+        self.postings.range(prefix.to_string()..).take_while(move |(term, _)| term.starts_with(prefix))
+    }
+
+    fn phrase_ids(&self, terms: &[String]) -> BTreeSet<u32> {
+        let Some(first_documents) = terms.first().and_then(|term| self.postings.get(term)) else {
+            return BTreeSet::new();
+        };
+
+        first_documents.iter()
+            .filter(|(id, first_positions)| {
+                first_positions.iter().any(|&start| {
+                    terms.iter().enumerate().skip(1).all(|(offset, term)| {
+                        self.postings.get(term)
+                            .and_then(|documents| documents.get(id))
+                            .is_some_and(|positions| positions.binary_search(&(start + offset as u32)).is_ok())
+                    })
+                })
+            })
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    // BM25 relevance of a note to the query
+    pub fn score(&self, id: u32, query: &SearchQuery) -> f64 {
+        let document_count = self.notes.len() as f64;
+        let average_length = self.notes.values().map(|note| note.length as f64).sum::<f64>() / document_count.max(1.0);
+        let document_length = self.notes.get(&id).map(|note| note.length).unwrap_or_default() as f64;
+
+        let mut terms: Vec<&String> = vec![];
+        for item in query.items() {
+            match item {
+                QueryItem::Term(term) => terms.push(term),
+                QueryItem::Prefix(prefix) => terms.extend(self.expand_prefix(prefix).map(|(term, _)| term)),
+                QueryItem::Phrase(phrase_terms) => terms.extend(phrase_terms),
+            }
+        }
+        terms.sort();
+        terms.dedup();
+
+        terms.into_iter()
+            .filter_map(|term| self.postings.get(term))
+            .filter_map(|documents| documents.get(&id).map(|positions| (documents.len() as f64, positions.len() as f64)))
+            .map(|(matching_documents, frequency)| {
+                let idf = ((document_count - matching_documents + 0.5) / (matching_documents + 0.5) + 1.0).ln();
+                idf * frequency * (BM25_K1 + 1.0) / (frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * document_length / average_length.max(1.0)))
+            })
+            .sum()
+    }
+}
+
+// lowercase words together with the byte range they were found at
+pub fn tokenize(text: &str) -> Vec<(String, Range<usize>)> {
+    let mut tokens = vec![];
+    let mut start = None;
+    for (index, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        let is_word_char = c.is_alphanumeric() || c == '_';
+        match (start, is_word_char) {
+            (None, true) => start = Some(index),
+            (Some(token_start), false) => {
+                tokens.push((text[token_start..index].to_lowercase(), token_start..index));
+                start = None;
+            },
+            _ => {},
+        }
+    }
+    tokens
+}
+
+// byte ranges of the words in `text` matched by any part of the query
+pub fn highlight_ranges(text: &str, query: &SearchQuery) -> Vec<Range<usize>> {
+    let mut terms: HashMap<&str, bool> = HashMap::new();
+    for item in query.items() {
+        match item {
+            QueryItem::Term(term) => { terms.insert(term, false); },
+            QueryItem::Prefix(prefix) => { terms.insert(prefix, true); },
+            QueryItem::Phrase(phrase_terms) => terms.extend(phrase_terms.iter().map(|term| (term.as_str(), false))),
+        }
+    }
+
+    tokenize(text).into_iter()
+        .filter(|(token, _)| terms.iter().any(|(term, is_prefix)| if *is_prefix { token.starts_with(term) } else { token == term }))
+        .map(|(_, range)| range)
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineMatch<'a> {
+    // counted from 1
+    pub number: usize,
+    pub line: &'a str,
+    // false for context lines
+    pub is_match: bool,
+    // byte ranges of the matches within the line
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Lines of the text matching the regex together with up to `before` and `after` lines of context
+/// around them, grouped into runs of neighbouring lines the way grep prints them.
+pub fn regex_matches<'a>(text: &'a str, regex: &Regex, before: usize, after: usize) -> Vec<Vec<LineMatch<'a>>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let matching_lines: Vec<usize> = (0..lines.len()).filter(|&index| regex.is_match(lines[index])).collect();
+
+    let mut groups: Vec<Vec<LineMatch>> = vec![];
+    // lines before this one are printed already, possibly as context of an earlier match
+    let mut next_line = 0;
+    for &index in &matching_lines {
+        let start = index.saturating_sub(before).max(next_line);
+        let end = (index + after + 1).min(lines.len()).max(next_line);
+        if groups.is_empty() || start > next_line {
+            groups.push(vec![]);
+        }
+
+        let group = groups.last_mut().unwrap();
+        for (number, &line) in lines.iter().enumerate().take(end).skip(start) {
+            let is_match = matching_lines.binary_search(&number).is_ok();
+            let ranges = if is_match {
+                regex.find_iter(line).filter(|m| !m.is_empty()).map(|m| m.range()).collect()
+            } else {
+                vec![]
+            };
+            group.push(LineMatch { number: number + 1, line, is_match, ranges });
+        }
+        next_line = end;
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_index(contents: &[&str]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for (id, content) in (1..).zip(contents) {
+            index.add(id, content, Fingerprint::default());
+        }
+        index
+    }
+
+    fn query(items: Vec<QueryItem>) -> SearchQuery {
+        SearchQuery { clauses: vec![items] }
+    }
+
+    fn term(word: &str) -> QueryItem {
+        QueryItem::Term(word.to_string())
+    }
+
+    fn hit_ids(index: &SearchIndex, query: &SearchQuery) -> Vec<u32> {
+        index.search(query).into_iter().map(|hit| hit.id).collect()
+    }
+
+    #[test]
+    fn tokenizes_words_with_their_byte_ranges() {
+        assert_eq!(tokenize("Hello, wörld_1 x"), vec![
+            ("hello".to_string(), 0..5),
+            ("wörld_1".to_string(), 7..15),
+            ("x".to_string(), 16..17),
+        ]);
+        assert_eq!(tokenize(" -- "), vec![]);
+    }
+
+    #[test]
+    fn ranks_frequent_terms_in_short_notes_first() {
+        let index = build_index(&["apple banana cherry durian elderberry", "apple apple banana", "cherry"]);
+        let hits = index.search(&query(vec![term("apple")]));
+        assert_eq!(hits.iter().map(|hit| hit.id).collect::<Vec<_>>(), vec![2, 1]);
+        assert!(hits[0].score > hits[1].score);
+
+        // a rare term weighs more than a common one
+        let index = build_index(&["banana", "durian", "banana", "banana"]);
+        let either = SearchQuery { clauses: vec![vec![term("banana")], vec![term("durian")]] };
+        assert_eq!(hit_ids(&index, &either), vec![2, 1, 3, 4]);
+    }
+
+    #[test]
+    fn matches_phrases_only_in_order_and_next_to_each_other() {
+        let index = build_index(&["big red dog", "red big dog", "big, red!", "big old red"]);
+        let phrase = QueryItem::Phrase(vec!["big".to_string(), "red".to_string()]);
+        assert_eq!(index.matching_ids(&phrase), BTreeSet::from([1, 3]));
+    }
+
+    #[test]
+    fn expands_prefixes_to_every_term_starting_with_them() {
+        let index = build_index(&["deploy", "deployment", "depot", "redeploy"]);
+        assert_eq!(index.matching_ids(&QueryItem::Prefix("deploy".to_string())), BTreeSet::from([1, 2]));
+        assert_eq!(index.matching_ids(&QueryItem::Prefix("dep".to_string())), BTreeSet::from([1, 2, 3]));
+        assert!(index.score(2, &query(vec![QueryItem::Prefix("deploy".to_string())])) > 0.0);
+    }
+
+    #[test]
+    fn removes_every_posting_of_a_note() {
+        let mut index = build_index(&["apple pie", "apple tart"]);
+        index.remove(1);
+        assert_eq!(index.matching_ids(&term("apple")), BTreeSet::from([2]));
+        assert_eq!(index.matching_ids(&term("pie")), BTreeSet::new());
+        assert_eq!(index.terms(2), BTreeMap::from([("apple".to_string(), vec![0]), ("tart".to_string(), vec![1])]));
+    }
+
+    // line numbers of every group, with the matching ones marked by a `*`
+    fn regex_groups(text: &str, pattern: &str, before: usize, after: usize) -> Vec<Vec<String>> {
+        regex_matches(text, &Regex::new(pattern).unwrap(), before, after).into_iter()
+            .map(|group| group.into_iter().map(|line| format!("{}{}", line.number, if line.is_match { "*" } else { "" })).collect())
+            .collect()
+    }
+
+    #[test]
+    fn groups_regex_matches_with_their_context() {
+        let text = "a\nmatch one\nb\nc\nd\nmatch two\nmatch three\ne";
+        assert_eq!(regex_groups(text, "match", 1, 1), vec![vec!["1", "2*", "3"], vec!["5", "6*", "7*", "8"]]);
+        assert_eq!(regex_groups(text, "match", 0, 0), vec![vec!["2*"], vec!["6*", "7*"]]);
+        assert_eq!(regex_groups(text, "match", 5, 0), vec![vec!["1", "2*", "3", "4", "5", "6*", "7*"]]);
+
+        let lines = &regex_matches(text, &Regex::new("t").unwrap(), 0, 0)[1];
+        assert_eq!(lines[1].ranges, vec![2..3, 6..7]);
+    }
+
+    #[test]
+    fn merges_groups_whose_context_touches() {
+        let text = "a\nmatch\nb\nc\nmatch\nd\ne";
+        assert_eq!(regex_groups(text, "match", 1, 1), vec![vec!["1", "2*", "3", "4", "5*", "6"]]);
+        assert_eq!(regex_groups(text, "match", 0, 1), vec![vec!["2*", "3"], vec!["5*", "6"]]);
+        assert_eq!(regex_groups("match\nmatch", "match", 3, 3), vec![vec!["1*", "2*"]]);
+    }
+}
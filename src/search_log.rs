@@ -0,0 +1,171 @@
+use std::{collections::BTreeMap, io, path::{Path, PathBuf}};
+use serde::{Serialize, Deserialize};
+use crate::error::Result;
+use crate::search::{Fingerprint, SearchIndex};
+use crate::storage;
+
+// the log is only rewritten when it holds at least this many records and more than half of them are stale
+const COMPACTION_MIN_RECORDS: usize = 64;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum IndexRecord {
+    Put { id: u32, fingerprint: Fingerprint, terms: BTreeMap<String, Vec<u32>> },
+    Delete { id: u32 },
+}
+
+/// Append-only log of the search index.
+///
+/// Every change appends a single record holding the term positions of one note, so saving costs
+/// the size of that note rather than of the whole index, and stale records are dropped by a
+/// periodic compaction. The notes can always be indexed again, so a log that cannot be read is
+/// started over instead of reported.
+#[derive(Debug)]
+pub struct SearchLog {
+    log_file: PathBuf,
+    record_count: usize,
+}
+
+impl SearchLog {
+    pub fn open(directory: &Path) -> Result<(SearchLog, SearchIndex)> {
+        let mut log = SearchLog { log_file: directory.join("search_index.log"), record_count: 0 };
+        // the whole index used to be rewritten into this file on every save
+        let json_index_file = directory.join("search_index.json");
+        if json_index_file.exists() {
+            storage::remove_durably(&json_index_file)?;
+        }
+
+        let data = match std::fs::read_to_string(&log.log_file) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((log, SearchIndex::default())),
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => return log.start_over(),
+            Err(e) => return Err(e.into()),
+        };
+        // a record without its newline was cut short, and the next one would be appended to the same line
+        if !data.is_empty() && !data.ends_with('\n') {
+            return log.start_over();
+        }
+
+        let mut index = SearchIndex::default();
+        for line in data.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(IndexRecord::Put { id, fingerprint, terms }) => index.insert(id, fingerprint, terms),
+                Ok(IndexRecord::Delete { id }) => index.remove(id),
+                Err(_) => return log.start_over(),
+            }
+            log.record_count += 1;
+        }
+        Ok((log, index))
+    }
+
+    pub fn put(&mut self, index: &mut SearchIndex, id: u32, content: &str, fingerprint: Fingerprint) -> Result<()> {
+        index.add(id, content, fingerprint);
+        self.append(&IndexRecord::Put { id, fingerprint, terms: index.terms(id) })?;
+        self.compact_if_needed(index)
+    }
+
+    pub fn remove(&mut self, index: &mut SearchIndex, id: u32) -> Result<()> {
+        if index.fingerprint(id).is_none() {
+            return Ok(());
+        }
+        index.remove(id);
+        self.append(&IndexRecord::Delete { id })?;
+        self.compact_if_needed(index)
+    }
+
+    pub fn compact(&mut self, index: &SearchIndex) -> Result<()> {
+        let ids = index.ids();
+        let mut data = String::new();
+        for &id in &ids {
+            let fingerprint = index.fingerprint(id).unwrap_or_default();
+            data.push_str(&serde_json::to_string(&IndexRecord::Put { id, fingerprint, terms: index.terms(id) })?);
+            data.push('\n');
+        }
+        storage::write_atomically(&self.log_file, data.as_bytes())?;
+        self.record_count = ids.len();
+        Ok(())
+    }
+
+    fn compact_if_needed(&mut self, index: &SearchIndex) -> Result<()> {
+        if self.record_count >= COMPACTION_MIN_RECORDS && self.record_count > index.ids().len() * 2 {
+            self.compact(index)?;
+        }
+        Ok(())
+    }
+
+    fn append(&mut self, record: &IndexRecord) -> Result<()> {
+        storage::append_durably(&self.log_file, format!("{}\n", serde_json::to_string(record)?).as_bytes())?;
+        self.record_count += 1;
+        Ok(())
+    }
+
+    fn start_over(self) -> Result<(SearchLog, SearchIndex)> {
+        storage::remove_durably(&self.log_file)?;
+        Ok((SearchLog { record_count: 0, ..self }, SearchIndex::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::QueryItem;
+
+    fn temp_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("rustic_notes_search_log_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn matching_ids(index: &SearchIndex, term: &str) -> Vec<u32> {
+        index.matching_ids(&QueryItem::Term(term.to_string())).into_iter().collect()
+    }
+
+    #[test]
+    fn replays_puts_and_deletes_on_open() {
+        let directory = temp_directory("replay");
+        let (mut log, mut index) = SearchLog::open(&directory).unwrap();
+        log.put(&mut index, 1, "apple pie", Fingerprint::default()).unwrap();
+        log.put(&mut index, 2, "apple tart", Fingerprint::default()).unwrap();
+        log.put(&mut index, 1, "banana bread", Fingerprint { length: 12, ..Fingerprint::default() }).unwrap();
+        log.remove(&mut index, 2).unwrap();
+
+        let (_, index) = SearchLog::open(&directory).unwrap();
+        assert_eq!(matching_ids(&index, "apple"), Vec::<u32>::new());
+        assert_eq!(matching_ids(&index, "bread"), vec![1]);
+        assert_eq!(index.fingerprint(1).map(|fingerprint| fingerprint.length), Some(12));
+        assert_eq!(index.fingerprint(2), None);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn starts_over_after_a_partially_written_record() {
+        let directory = temp_directory("partial");
+        let (mut log, mut index) = SearchLog::open(&directory).unwrap();
+        log.put(&mut index, 1, "apple pie", Fingerprint::default()).unwrap();
+        storage::append_durably(&directory.join("search_index.log"), br#"{"op":"put","id":2"#).unwrap();
+
+        let (_, index) = SearchLog::open(&directory).unwrap();
+        assert!(index.ids().is_empty());
+        assert!(!directory.join("search_index.log").exists());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn compacts_stale_records() {
+        let directory = temp_directory("compaction");
+        let (mut log, mut index) = SearchLog::open(&directory).unwrap();
+        for revision in 0..COMPACTION_MIN_RECORDS {
+            log.put(&mut index, 1, &format!("revision r{revision}"), Fingerprint::default()).unwrap();
+        }
+        log.put(&mut index, 2, "second note", Fingerprint::default()).unwrap();
+
+        let data = std::fs::read_to_string(directory.join("search_index.log")).unwrap();
+        assert!(data.lines().count() < COMPACTION_MIN_RECORDS, "the log was not compacted");
+        let (_, index) = SearchLog::open(&directory).unwrap();
+        assert_eq!(matching_ids(&index, &format!("r{}", COMPACTION_MIN_RECORDS - 1)), vec![1]);
+        assert_eq!(matching_ids(&index, "r0"), Vec::<u32>::new());
+        assert_eq!(matching_ids(&index, "second"), vec![2]);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
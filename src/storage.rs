@@ -0,0 +1,99 @@
+use std::{fs, io::{self, Write}, path::{Path, PathBuf}};
+use crate::time_utils;
+
+// writes go to a temporary sibling first and are renamed over the target only once they are on disk,
+// so a crash leaves either the old or the new file behind, never a truncated one
+pub fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut temp_file_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_file_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_file_name);
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&temp_path, path)?;
+    sync_directory(path)
+}
+
+pub fn append_durably(path: &Path, data: &[u8]) -> io::Result<u64> {
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let offset = file.metadata()?.len();
+    file.write_all(data)?;
+    file.sync_data()?;
+    Ok(offset)
+}
+
+pub fn remove_durably(path: &Path) -> io::Result<()> {
+    fs::remove_file(path)?;
+    sync_directory(path)
+}
+
+// renames and removals are only durable once the directory holding the file is flushed as well
+fn sync_directory(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if cfg!(unix) => fs::File::open(if parent.as_os_str().is_empty() { Path::new(".") } else { parent })?.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+/// Copies the given entries of `directory` into a new `backups/<timestamp>` snapshot and removes
+/// the oldest snapshots so that at most `keep` of them remain.
+pub fn snapshot(directory: &Path, entries: &[&str], keep: usize) -> io::Result<PathBuf> {
+    let backups_directory = directory.join("backups");
+    let mut snapshot_name = time_utils::now().to_string();
+    let mut suffix = 1;
+    while backups_directory.join(&snapshot_name).exists() {
+        snapshot_name = format!("{}-{suffix}", time_utils::now());
+        suffix += 1;
+    }
+
+    let snapshot_directory = backups_directory.join(snapshot_name);
+    fs::create_dir_all(&snapshot_directory)?;
+    for entry in entries {
+        copy_recursively(&directory.join(entry), &snapshot_directory.join(entry))?;
+    }
+
+    let snapshots = list_snapshots(directory)?;
+    for old_snapshot in snapshots.iter().take(snapshots.len().saturating_sub(keep)) {
+        fs::remove_dir_all(old_snapshot)?;
+    }
+    Ok(snapshot_directory)
+}
+
+// oldest first
+pub fn list_snapshots(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(directory.join("backups")) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut snapshots = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            snapshots.push(path);
+        }
+    }
+    snapshots.sort_by_key(|path| snapshot_time(path));
+    Ok(snapshots)
+}
+
+pub fn snapshot_time(snapshot: &Path) -> (u64, u32) {
+    let name = snapshot.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let (time, suffix) = name.split_once('-').unwrap_or((name, "0"));
+    (time.parse().unwrap_or_default(), suffix.parse().unwrap_or_default())
+}
+
+fn copy_recursively(source: &Path, destination: &Path) -> io::Result<()> {
+    if source.is_dir() {
+        fs::create_dir_all(destination)?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+    } else if source.exists() {
+        fs::copy(source, destination)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+use crate::cli_command::{ArgumentType, CliCommandArgument, CliCommandBuilder, CliCommand, CliCommandOption, ParsedArgs};
+use crate::note_commands::{open_store, split_tags};
+use crate::print_utils;
+use rustic_notes::{notes::TagNode, Result, RusticError};
+
+pub fn build_tag_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("tag")
+        .set_description("Manage the tags of notes")
+        .add_subcommand(&build_tag_add_command())
+        .add_subcommand(&build_tag_remove_command())
+        .add_subcommand(&build_tag_rename_command())
+        .build()
+}
+
+pub fn build_tags_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("tags")
+        .set_description("List every tag together with the number of notes having it")
+        .add_option(
+            &CliCommandOption {
+                name: "tree".to_string(),
+                short_name: None,
+                description: Some("Show the tag hierarchy, counting the notes below every level".to_string()),
+                is_flag: true,
+                ..Default::default()
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let store = open_store(&args)?;
+            if args.flag("tree") {
+                let tree = TagNode::build(&store.list()?);
+                if tree.children.is_empty() {
+                    println!("{}", print_utils::colorize(print_utils::Color::warning(), "No tags found."));
+                } else {
+                    for (name, child) in &tree.children {
+                        println!("{name} ({})", child.note_count);
+                        print_tag_tree(child, "");
+                    }
+                }
+                return Ok(());
+            }
+
+            let tags = store.tags()?;
+            if tags.is_empty() {
+                println!("{}", print_utils::colorize(print_utils::Color::warning(), "No tags found."));
+                return Ok(());
+            }
+
+            let longest_tag_len = tags.keys().map(|tag| tag.chars().count()).max().unwrap_or(0);
+            println!("Tags:");
+            for (tag, count) in tags {
+                println!("{tag:<longest_tag_len$}  {count}");
+            }
+            Ok(())
+        }).build()
+}
+
+fn build_tag_add_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("add")
+        .set_description("Add tags to a note")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id of the note".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .add_argument(
+            &CliCommandArgument {
+                name: "tags".to_string(),
+                description: Some("Tags to add, separated by spaces or commas".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: true
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let id: u32 = args.get_required("id")?;
+            let tags = get_tags_argument(&args)?;
+            let note = open_store(&args)?.add_tags(id, &tags)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Note {id} tagged: {}.", note.tags.join(", ")).as_str()));
+            Ok(())
+        }).build()
+}
+
+fn build_tag_remove_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("rm")
+        .add_alias("remove")
+        .set_description("Remove tags from a note")
+        .add_argument(
+            &CliCommandArgument {
+                name: "id".to_string(),
+                description: Some("Id of the note".to_string()),
+                value_type: ArgumentType::Number,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .add_argument(
+            &CliCommandArgument {
+                name: "tags".to_string(),
+                description: Some("Tags to remove, separated by spaces or commas".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: true
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let id: u32 = args.get_required("id")?;
+            let tags = get_tags_argument(&args)?;
+            open_store(&args)?.remove_tags(id, &tags)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Removed {} from note {id}.", tags.join(", ")).as_str()));
+            Ok(())
+        }).build()
+}
+
+fn build_tag_rename_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("rename")
+        .set_description("Rename a tag on every note having it")
+        .add_argument(
+            &CliCommandArgument {
+                name: "tag".to_string(),
+                description: Some("Tag to rename, its subtags are renamed along with it".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .add_argument(
+            &CliCommandArgument {
+                name: "new_tag".to_string(),
+                description: Some("New name of the tag".to_string()),
+                value_type: ArgumentType::Text,
+                is_required: true,
+                is_variadic: false
+            }
+        )
+        .set_action(|args: ParsedArgs| {
+            let tag: String = args.get_required("tag")?;
+            let new_tag: String = args.get_required("new_tag")?;
+            let renamed_ids = open_store(&args)?.rename_tag(&tag, &new_tag)?;
+            println!("{}", print_utils::colorize(print_utils::Color::success(), format!("Tag '{tag}' renamed to '{new_tag}' on {} notes.", renamed_ids.len()).as_str()));
+            Ok(())
+        }).build()
+}
+
+fn print_tag_tree(node: &TagNode, indent: &str) {
+    for (index, (name, child)) in node.children.iter().enumerate() {
+        let is_last = index + 1 == node.children.len();
+        println!("{indent}{}{name} ({})", if is_last { "└── " } else { "├── " }, child.note_count);
+        print_tag_tree(child, &format!("{indent}{}", if is_last { "    " } else { "│   " }));
+    }
+}
+
+fn get_tags_argument(args: &ParsedArgs) -> Result<Vec<String>> {
+    let tags = split_tags(&args.get_many::<String>("tags")?);
+    if tags.is_empty() {
+        return Err(RusticError::Usage("At least one tag is required.".to_string()));
+    }
+    Ok(tags)
+}
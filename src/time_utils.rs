@@ -0,0 +1,126 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::error::{Result, RusticError};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+// timestamps are kept as unix seconds and always shown in UTC, 0 marks notes saved before timestamps existed
+pub fn format_timestamp(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return String::from("unknown");
+    }
+
+    let (year, month, day) = civil_from_days((timestamp / SECONDS_PER_DAY) as i64);
+    let seconds_of_day = timestamp % SECONDS_PER_DAY;
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}", seconds_of_day / 3600, seconds_of_day % 3600 / 60)
+}
+
+/// Parses `YYYY-MM-DD`, `YYYY-MM-DD HH:MM` or `YYYY-MM-DDTHH:MM:SS` (UTC) into the span of time it
+/// covers, as a `[start, end)` pair of unix timestamps. A plain date spans the whole day.
+pub fn parse_date_span(input: &str) -> Result<(u64, u64)> {
+    let invalid_date = || RusticError::Usage(format!("Invalid date: '{input}', expected YYYY-MM-DD or YYYY-MM-DD HH:MM."));
+
+    let input = input.trim();
+    let (date, time) = match input.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (input, None),
+    };
+
+    let date_parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = date_parts[..] else {
+        return Err(invalid_date());
+    };
+    let year: i64 = year.parse().map_err(|_| invalid_date())?;
+    let month: u32 = month.parse().map_err(|_| invalid_date())?;
+    let day: u32 = day.parse().map_err(|_| invalid_date())?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || year < 1970 {
+        return Err(invalid_date());
+    }
+    let day_start = days_from_civil(year, month, day) as u64 * SECONDS_PER_DAY;
+
+    let Some(time) = time else {
+        return Ok((day_start, day_start + SECONDS_PER_DAY));
+    };
+
+    let time_parts: Vec<u64> = time.split(':')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid_date()))
+        .collect::<Result<_>>()?;
+    match time_parts[..] {
+        [hours, minutes] if hours < 24 && minutes < 60 => {
+            let start = day_start + hours * 3600 + minutes * 60;
+            Ok((start, start + 60))
+        },
+        [hours, minutes, seconds] if hours < 24 && minutes < 60 && seconds < 60 => {
+            let start = day_start + hours * 3600 + minutes * 60 + seconds;
+            Ok((start, start + 1))
+        },
+        _ => Err(invalid_date()),
+    }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// both conversions follow Howard Hinnant's "chrono-compatible low-level date algorithms"
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_date_spans_the_whole_day() {
+        assert_eq!(parse_date_span("1970-01-01").unwrap(), (0, SECONDS_PER_DAY));
+        assert_eq!(parse_date_span("2024-02-29").unwrap(), (1709164800, 1709164800 + SECONDS_PER_DAY));
+    }
+
+    #[test]
+    fn a_time_spans_its_minute_or_second() {
+        assert_eq!(parse_date_span("2024-02-29 12:30").unwrap(), (1709209800, 1709209860));
+        assert_eq!(parse_date_span("2024-02-29T12:30:15").unwrap(), (1709209815, 1709209816));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for input in ["2023-02-29", "2024-13-01", "2024-04-31", "1969-12-31", "2024-01-01 24:00", "2024-01-01 12", "yesterday"] {
+            assert!(parse_date_span(input).is_err(), "{input} was accepted");
+        }
+    }
+
+    #[test]
+    fn formats_the_start_of_a_parsed_span() {
+        let (start, _) = parse_date_span("2026-10-18 09:05").unwrap();
+        assert_eq!(format_timestamp(start), "2026-10-18 09:05");
+        assert_eq!(format_timestamp(0), "unknown");
+    }
+}
@@ -0,0 +1,405 @@
+use crate::cli_command::{CliCommandBuilder, CliCommand, ParsedArgs};
+use crate::note_commands;
+use crate::print_utils;
+use crossterm::{cursor, event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers}, queue, style, terminal};
+use rustic_notes::{notes::TagFilter, query::Query, search::SearchIndex, time_utils, NoteStore, Result, RusticNote};
+use std::io::{self, Write};
+
+pub fn build_tui_command() -> CliCommand {
+    CliCommandBuilder::default()
+        .set_name("tui")
+        .set_description("Browse and edit notes in an interactive full-screen interface")
+        .set_action(|args: ParsedArgs| {
+            let mut app = App::new(note_commands::open_store(&args)?)?;
+            let _terminal = TerminalGuard::enter()?;
+            app.run()
+        }).build()
+}
+
+const HELP: &str = "j/k move  / search  t filter tag  n new  e edit  a tag  d delete  q quit";
+
+#[derive(PartialEq)]
+enum Mode {
+    Browse,
+    Search,
+    TagFilter,
+    Tag,
+    ConfirmDelete,
+}
+
+struct App {
+    store: NoteStore,
+    notes: Vec<RusticNote>,
+    index: SearchIndex,
+    visible_notes: Vec<RusticNote>,
+    selected: usize,
+    scroll: usize,
+    search_query: String,
+    tag_filter: String,
+    mode: Mode,
+    input: String,
+    status: Option<String>,
+}
+
+impl App {
+    fn new(store: NoteStore) -> Result<App> {
+        let mut app = App {
+            notes: store.list()?,
+            index: store.search_index()?,
+            store,
+            visible_notes: vec![],
+            selected: 0,
+            scroll: 0,
+            search_query: String::new(),
+            tag_filter: String::new(),
+            mode: Mode::Browse,
+            input: String::new(),
+            status: None,
+        };
+        app.refilter();
+        Ok(app)
+    }
+
+    fn run(&mut self) -> Result<()> {
+        let mut stdout = io::stdout();
+        loop {
+            self.draw(&mut stdout)?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if !self.handle_key(key)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    // returns false once the user asks to quit
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Ok(false);
+        }
+
+        match self.mode {
+            Mode::Browse => return self.handle_browse_key(key),
+            Mode::ConfirmDelete => {
+                if key.code == KeyCode::Char('y') {
+                    self.delete_selected();
+                } else {
+                    self.status = Some(String::from("Delete cancelled."));
+                }
+                self.mode = Mode::Browse;
+            },
+            Mode::Search | Mode::TagFilter | Mode::Tag => self.handle_input_key(key),
+        }
+        Ok(true)
+    }
+
+    fn handle_browse_key(&mut self, key: KeyEvent) -> Result<bool> {
+        self.status = None;
+        match key.code {
+            KeyCode::Char('q') => return Ok(false),
+            KeyCode::Esc if self.search_query.is_empty() && self.tag_filter.is_empty() => return Ok(false),
+            KeyCode::Esc => {
+                self.search_query.clear();
+                self.tag_filter.clear();
+                self.refilter();
+            },
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(self.visible_notes.len().saturating_sub(1)),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected = self.visible_notes.len().saturating_sub(1),
+            KeyCode::Char('/') => self.start_input(Mode::Search, self.search_query.clone()),
+            KeyCode::Char('t') => self.start_input(Mode::TagFilter, self.tag_filter.clone()),
+            KeyCode::Char('a') if self.selected_note().is_some() => self.start_input(Mode::Tag, String::new()),
+            KeyCode::Char('d') if self.selected_note().is_some() => self.mode = Mode::ConfirmDelete,
+            KeyCode::Char('n') => self.create_note()?,
+            KeyCode::Char('e') | KeyCode::Enter if self.selected_note().is_some() => self.edit_selected()?,
+            _ => {},
+        }
+        Ok(true)
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => {
+                if self.mode == Mode::Search {
+                    self.search_query.clear();
+                } else if self.mode == Mode::TagFilter {
+                    self.tag_filter.clear();
+                }
+                self.input.clear();
+                self.mode = Mode::Browse;
+                self.refilter();
+                return;
+            },
+            KeyCode::Enter => {
+                if self.mode == Mode::Tag {
+                    self.tag_selected();
+                }
+                self.mode = Mode::Browse;
+                return;
+            },
+            KeyCode::Backspace => { self.input.pop(); },
+            KeyCode::Char(c) => self.input.push(c),
+            _ => return,
+        }
+
+        // search and tag filter narrow the list while typing
+        match self.mode {
+            Mode::Search => self.search_query = self.input.clone(),
+            Mode::TagFilter => self.tag_filter = self.input.trim().to_string(),
+            _ => return,
+        }
+        self.refilter();
+    }
+
+    fn start_input(&mut self, mode: Mode, input: String) {
+        self.mode = mode;
+        self.input = input;
+    }
+
+    fn refilter(&mut self) {
+        let selected_id = self.selected_note().map(|note| note.id);
+        // the word being typed matches as a prefix, so results show up before it is finished
+        let last_word = self.search_query.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let query = if last_word.ends_with(|c: char| c.is_alphanumeric()) && !last_word.contains(':') {
+            format!("{}*", self.search_query)
+        } else {
+            self.search_query.clone()
+        };
+        // a query without any words yet does not filter anything
+        let query = Query::parse(&query).ok().map(|_| query);
+        let tag_filter = TagFilter { tags: note_commands::split_tags(std::slice::from_ref(&self.tag_filter)), ..TagFilter::default() };
+        self.visible_notes = note_commands::search_notes(self.notes.clone(), &self.index, query.as_deref(), &tag_filter).unwrap_or_default();
+
+        // keep the cursor on the same note if it is still visible
+        self.selected = selected_id
+            .and_then(|id| self.visible_notes.iter().position(|note| note.id == id))
+            .unwrap_or(0);
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        self.notes = self.store.list()?;
+        self.index = self.store.search_index()?;
+        self.refilter();
+        Ok(())
+    }
+
+    fn selected_note(&self) -> Option<&RusticNote> {
+        self.visible_notes.get(self.selected)
+    }
+
+    fn create_note(&mut self) -> Result<()> {
+        let content = match TerminalGuard::suspend(|| note_commands::get_from_editor(None))? {
+            Ok(content) => content,
+            Err(e) => {
+                self.status = Some(e.to_string());
+                return Ok(());
+            }
+        };
+        if content.trim().is_empty() {
+            self.status = Some(String::from("Note content is empty, not creating note."));
+            return Ok(());
+        }
+
+        match self.store.create(content.trim(), None, vec![]) {
+            Ok(note) => {
+                self.status = Some(format!("Note {} created.", note.id));
+                self.reload()?;
+                if let Some(index) = self.visible_notes.iter().position(|n| n.id == note.id) {
+                    self.selected = index;
+                }
+            },
+            Err(e) => self.status = Some(e.to_string()),
+        }
+        Ok(())
+    }
+
+    fn edit_selected(&mut self) -> Result<()> {
+        let Some(mut note) = self.selected_note().cloned() else {
+            return Ok(());
+        };
+        let content = match TerminalGuard::suspend(|| note_commands::get_from_editor(Some(note.content.clone())))? {
+            Ok(content) => content,
+            Err(e) => {
+                self.status = Some(e.to_string());
+                return Ok(());
+            }
+        };
+
+        note.content = content.trim().to_string();
+        self.status = Some(match self.store.update(&note) {
+            Ok(_) => format!("Note {} saved.", note.id),
+            Err(e) => e.to_string(),
+        });
+        self.reload()
+    }
+
+    // "tag" adds a tag, "-tag" removes it
+    fn tag_selected(&mut self) {
+        let Some(id) = self.selected_note().map(|note| note.id) else {
+            return;
+        };
+        let input = std::mem::take(&mut self.input);
+        let input = input.trim();
+        if input.is_empty() {
+            return;
+        }
+
+        let result = match input.strip_prefix('-') {
+            Some(tag) => self.store.remove_tags(id, &[tag.to_string()]),
+            None => self.store.add_tags(id, &[input.to_string()]),
+        };
+        self.status = Some(match result {
+            Ok(_) => format!("Tags of note {id} updated."),
+            Err(e) => e.to_string(),
+        });
+        if let Err(e) = self.reload() {
+            self.status = Some(e.to_string());
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(id) = self.selected_note().map(|note| note.id) else {
+            return;
+        };
+        let index = self.selected;
+        self.status = Some(match self.store.delete(id) {
+            Ok(_) => format!("Note {id} deleted."),
+            Err(e) => e.to_string(),
+        });
+        if let Err(e) = self.reload() {
+            self.status = Some(e.to_string());
+        }
+        // move on to the note that took the place of the deleted one
+        self.selected = index.min(self.visible_notes.len().saturating_sub(1));
+    }
+
+    fn draw(&mut self, stdout: &mut io::Stdout) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        let list_width = (width * 2 / 5).max(20).min(width);
+        let preview_width = width.saturating_sub(list_width + 3);
+        let body_height = height.saturating_sub(2);
+
+        // keep the selected note inside the visible part of the list
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + body_height {
+            self.scroll = self.selected + 1 - body_height;
+        }
+
+        queue!(stdout, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+
+        let mut header = format!(" RusticNotes  {} notes", self.visible_notes.len());
+        if !self.tag_filter.is_empty() {
+            header.push_str(&format!("  tag: {}", self.tag_filter));
+        }
+        if !self.search_query.is_empty() {
+            header.push_str(&format!("  search: {}", self.search_query));
+        }
+        queue!(stdout, style::Print(print_utils::colorize(print_utils::Color::success(), &fit(&header, width))))?;
+
+        let preview = self.preview_lines(preview_width);
+        for row in 0..body_height {
+            queue!(stdout, cursor::MoveTo(0, (row + 1) as u16))?;
+
+            let index = self.scroll + row;
+            if let Some(note) = self.visible_notes.get(index) {
+                let label = note.title().unwrap_or_else(|| note.content.lines().next().unwrap_or_default().to_string());
+                let line = fit(&format!("{:>4} {label}", note.id), list_width);
+                if index == self.selected {
+                    queue!(stdout, style::SetAttribute(style::Attribute::Reverse), style::Print(line), style::SetAttribute(style::Attribute::Reset))?;
+                } else {
+                    queue!(stdout, style::Print(line))?;
+                }
+            } else {
+                queue!(stdout, style::Print(" ".repeat(list_width)))?;
+            }
+
+            queue!(stdout, style::Print(" │ "), style::Print(preview.get(row).map_or("", |line| line.as_str())))?;
+        }
+
+        queue!(stdout, cursor::MoveTo(0, height.saturating_sub(1) as u16))?;
+        let status_line = match self.mode {
+            Mode::Browse => self.status.clone().unwrap_or_else(|| HELP.to_string()),
+            Mode::Search => format!("/{}", self.input),
+            Mode::TagFilter => format!("tag filter: {}", self.input),
+            Mode::Tag => format!("add tag (prefix with - to remove): {}", self.input),
+            Mode::ConfirmDelete => String::from("delete this note? (y/n)"),
+        };
+        queue!(stdout, style::Print(print_utils::colorize(print_utils::Color::warning(), &fit(&status_line, width))))?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn preview_lines(&self, width: usize) -> Vec<String> {
+        let Some(note) = self.selected_note() else {
+            return vec![String::from("No notes found.")];
+        };
+
+        let mut lines = vec![
+            format!("#{}  {}", note.id, note.tags.join(", ")),
+            format!("created {}, updated {}", time_utils::format_timestamp(note.created_at), time_utils::format_timestamp(note.updated_at)),
+            String::new(),
+        ];
+        for line in note.content.lines() {
+            lines.extend(wrap(line, width));
+        }
+        lines.into_iter().map(|line| fit(&line, width)).collect()
+    }
+}
+
+// puts the terminal into raw mode on an alternate screen and restores it when dropped, even on errors
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<TerminalGuard> {
+        terminal::enable_raw_mode()?;
+        queue!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        io::stdout().flush()?;
+        Ok(TerminalGuard)
+    }
+
+    fn leave() -> io::Result<()> {
+        queue!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+        io::stdout().flush()?;
+        terminal::disable_raw_mode()
+    }
+
+    // hands the terminal over to an external program, such as the editor, for the duration of `f`
+    fn suspend<T>(f: impl FnOnce() -> T) -> Result<T> {
+        TerminalGuard::leave()?;
+        let result = f();
+        terminal::enable_raw_mode()?;
+        queue!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        io::stdout().flush()?;
+        Ok(result)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = TerminalGuard::leave();
+    }
+}
+
+fn fit(text: &str, width: usize) -> String {
+    let mut fitted: String = text.chars().filter(|c| !c.is_control()).take(width).collect();
+    let length = fitted.chars().count();
+    fitted.push_str(&" ".repeat(width - length));
+    fitted
+}
+
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    if width == 0 {
+        return vec![];
+    }
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars.chunks(width).map(|chunk| chunk.iter().collect()).collect()
+}